@@ -0,0 +1,19 @@
+//! Caching the rows of `filtered`, for the filter combinations hit repeatedly by
+//! expensive queries.
+use std::time::Duration;
+
+/// The cache `filtered_cached` consults before running the query, and fills with
+/// its output. The keys are the `cache_key` of the filters, `T` the output of
+/// `filtered`: the rows, with the total when paginated.
+///
+/// ```text
+/// impl<T: Clone + Send + 'static> FilterCache<T> for MemoryCache {
+///     fn get(&self, key: &str) -> Option<T> { ... }
+///     fn set(&self, key: &str, value: &T, ttl: Option<Duration>) { ... }
+/// }
+/// ```
+pub trait FilterCache<T> {
+    fn get(&self, key: &str) -> Option<T>;
+    /// `ttl` is the `cache_ttl` of the model, if any
+    fn set(&self, key: &str, value: &T, ttl: Option<Duration>);
+}
@@ -0,0 +1,396 @@
+//! The cursors of `filtered_after`, the position of the last row of a page in the
+//! order of a `sort`, for infinite scrolls on any sort of the model rather than
+//! by primary key only.
+use crate::{sort::NullsOrder, SortDirection};
+use std::{fmt, str::FromStr};
+
+/// The sort of the pages and the values of the last row served, written as
+/// `sort;value;...` with the `;` and `\` of the parts escaped and `\N` for a `NULL`.
+///
+/// ```text
+/// "title:desc,created_at;Diesel;2024-01-02 03:04:05;6a2f41a3-c54c-fce8-32d2-0324e1c32e22"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub sort: String,
+    /// The values of the columns of the sort, then of the primary key in the order
+    /// of `#[diesel(primary_key(...))]`, as the text the database reads them from
+    pub values: Vec<Option<String>>,
+}
+
+/// A cursor without the sort or a value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorError(pub String);
+
+impl fmt::Display for CursorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid cursor `{}`", self.0)
+    }
+}
+
+impl std::error::Error for CursorError {}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let escape = |part: &str| part.replace('\\', "\\\\").replace(';', "\\;");
+        f.write_str(&escape(&self.sort))?;
+        for value in &self.values {
+            match value {
+                Some(value) => write!(f, ";{}", escape(value))?,
+                None => f.write_str(";\\N")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = CursorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = vec![Some(String::new())];
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            let part = parts.last_mut().unwrap();
+            match (c, part.as_mut()) {
+                // `\N` is the whole part
+                ('\\', Some(text)) => match chars.next() {
+                    Some('N') if text.is_empty() => *part = None,
+                    Some('N') | None => return Err(CursorError(s.to_owned())),
+                    Some(c) => text.push(c),
+                },
+                (';', _) => parts.push(Some(String::new())),
+                (c, Some(text)) => text.push(c),
+                (_, None) => return Err(CursorError(s.to_owned())),
+            }
+        }
+        if parts.len() < 2 {
+            return Err(CursorError(s.to_owned()));
+        }
+        match parts.remove(0) {
+            Some(sort) => Ok(Cursor {
+                sort,
+                values: parts,
+            }),
+            None => Err(CursorError(s.to_owned())),
+        }
+    }
+}
+
+/// A column of the order of the pages and its value in the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CursorColumn<'a> {
+    /// The SQL of the column of the sorted rows
+    pub row: &'a str,
+    /// The SQL around the bound value of the cursor, e.g its cast to the type of the column
+    pub cursor: (&'a str, &'a str),
+    /// `None` for a `NULL`
+    pub value: Option<&'a str>,
+    pub direction: SortDirection,
+    /// Where the `NULL`s of the column are sorted, `None` for a column without
+    pub nulls: Option<NullsOrder>,
+    /// Whether the SQL of the cursor can be `NULL` with a value, e.g the column of
+    /// the row the value references
+    pub joined: bool,
+}
+
+/// The SQL condition of the rows after the cursor row, in parts followed by the value
+/// of the cursor to bind after them, if any.
+/// A single direction on columns without `NULL`s compares the rows,
+/// `(a, id) > ($1, $2)`, other orders expand it, `a < $1 OR (a = $2 AND id > $3)`,
+/// with the `NULL`s before or after the values like in the `ORDER BY`.
+pub fn after<'a>(columns: &[CursorColumn<'a>]) -> Vec<(String, Option<&'a str>)> {
+    let mut sql = Sql::default();
+    let operator = |direction: SortDirection| match direction {
+        SortDirection::Asc => " > ",
+        SortDirection::Desc => " < ",
+    };
+    if let Some(first) = columns.first() {
+        let compared = |column: &CursorColumn<'_>| {
+            column.direction == first.direction
+                && column.nulls.is_none()
+                && !column.joined
+                && column.value.is_some()
+        };
+        if columns.iter().all(compared) {
+            sql.push("(");
+            sql.push(&columns.iter().map(|c| c.row).collect::<Vec<_>>().join(", "));
+            sql.push(")");
+            sql.push(operator(first.direction));
+            sql.push("(");
+            for (i, column) in columns.iter().enumerate() {
+                if i > 0 {
+                    sql.push(", ");
+                }
+                sql.cursor(column);
+            }
+            sql.push(")");
+            return sql.parts;
+        }
+    }
+    let mut terms = 0;
+    for i in 0..columns.len() {
+        let column = &columns[i];
+        let nulls = column.nulls.unwrap_or(NullsOrder::Last);
+        // The `NULL`s of the cursor are after every row or before the other `NULL`s
+        if column.value.is_none() && nulls == NullsOrder::Last {
+            continue;
+        }
+        sql.push(match terms {
+            0 => "(",
+            _ => " OR (",
+        });
+        terms += 1;
+        for equal in &columns[..i] {
+            sql.push(equal.row);
+            match (equal.value, equal.joined) {
+                (None, _) => sql.push(" IS NULL"),
+                (Some(_), true) => {
+                    sql.push(" IS NOT DISTINCT FROM ");
+                    sql.cursor(equal);
+                }
+                (Some(_), false) => {
+                    sql.push(" = ");
+                    sql.cursor(equal);
+                }
+            }
+            sql.push(" AND ");
+        }
+        let row = column.row;
+        match (column.value, column.joined, column.nulls) {
+            (None, _, _) => {
+                sql.push(row);
+                sql.push(" IS NOT NULL");
+            }
+            // `COALESCE` is the order of the `NULL`s, the cursor is bound once in each
+            (Some(_), true, _) => {
+                sql.push("COALESCE(");
+                sql.push(row);
+                sql.push(operator(column.direction));
+                sql.cursor(column);
+                sql.push(", ");
+                sql.cursor(column);
+                match nulls {
+                    NullsOrder::First => {
+                        sql.push(" IS NULL AND ");
+                        sql.push(row);
+                        sql.push(" IS NOT NULL)");
+                    }
+                    NullsOrder::Last => sql.push(" IS NOT NULL)"),
+                }
+            }
+            (Some(_), false, Some(NullsOrder::Last)) => {
+                sql.push("(");
+                sql.push(row);
+                sql.push(operator(column.direction));
+                sql.cursor(column);
+                sql.push(" OR ");
+                sql.push(row);
+                sql.push(" IS NULL)");
+            }
+            (Some(_), false, _) => {
+                sql.push(row);
+                sql.push(operator(column.direction));
+                sql.cursor(column);
+            }
+        }
+        sql.push(")");
+    }
+    match (columns.is_empty(), terms) {
+        (true, _) => sql.push("TRUE"),
+        (false, 0) => sql.push("FALSE"),
+        _ => {}
+    }
+    sql.parts
+}
+
+/// The parts of `after`
+#[derive(Default)]
+struct Sql<'a> {
+    parts: Vec<(String, Option<&'a str>)>,
+}
+
+impl<'a> Sql<'a> {
+    fn push(&mut self, sql: &str) {
+        match self.parts.last_mut() {
+            Some((part, None)) => part.push_str(sql),
+            _ => self.parts.push((sql.to_owned(), None)),
+        }
+    }
+
+    fn cursor(&mut self, column: &CursorColumn<'a>) {
+        self.push(column.cursor.0);
+        match self.parts.last_mut() {
+            Some((_, value @ None)) => *value = column.value,
+            _ => self.parts.push((String::new(), column.value)),
+        }
+        self.push(column.cursor.1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cursor(sort: &str, values: &[Option<&str>]) -> Cursor {
+        Cursor {
+            sort: sort.to_owned(),
+            values: values.iter().map(|v| v.map(str::to_owned)).collect(),
+        }
+    }
+
+    fn column<'a>(row: &'a str, value: Option<&'a str>) -> CursorColumn<'a> {
+        CursorColumn {
+            row,
+            cursor: ("", ""),
+            value,
+            direction: SortDirection::Asc,
+            nulls: None,
+            joined: false,
+        }
+    }
+
+    /// The SQL of `after` with its binds numbered, and the bound values
+    fn render(columns: &[CursorColumn<'_>]) -> (String, Vec<String>) {
+        let mut sql = String::new();
+        let mut binds = vec![];
+        for (part, value) in after(columns) {
+            sql.push_str(&part);
+            if let Some(value) = value {
+                binds.push(value.to_owned());
+                sql.push_str(&format!("${}", binds.len()));
+            }
+        }
+        (sql, binds)
+    }
+
+    #[test]
+    fn cursors_round_trip() {
+        for cursor in [
+            cursor("title:desc,id", &[Some("Diesel"), Some("6")]),
+            cursor("a;b", &[Some(r"x;y\z"), Some(""), None]),
+            cursor("n", &[Some(r"\N"), Some("N"), None]),
+        ] {
+            assert_eq!(cursor.to_string().parse(), Ok(cursor));
+        }
+    }
+
+    #[test]
+    fn escapes_the_parts() {
+        assert_eq!(
+            cursor("a;b", &[Some(r"x;y\z"), None, Some(r"\N")]).to_string(),
+            r"a\;b;x\;y\\z;\N;\\N"
+        );
+        assert_eq!(
+            r"id;\N;a\;b".parse(),
+            Ok(cursor("id", &[None, Some("a;b")]))
+        );
+        assert_eq!("id;;".parse(), Ok(cursor("id", &[Some(""), Some("")])));
+    }
+
+    #[test]
+    fn rejects_malformed_cursors() {
+        for malformed in ["", "id", r"\N;1", r"id;a\N", r"id;\Na", r"id;1\"] {
+            assert_eq!(
+                malformed.parse::<Cursor>(),
+                Err(CursorError(malformed.to_owned())),
+                "{}",
+                malformed
+            );
+        }
+    }
+
+    #[test]
+    fn compares_the_rows_in_a_single_direction() {
+        let columns = [
+            CursorColumn {
+                cursor: ("CAST(", " AS DATE)"),
+                ..column("created_at", Some("2024-01-02"))
+            },
+            column("id", Some("6")),
+        ];
+        assert_eq!(
+            render(&columns),
+            (
+                "(created_at, id) > (CAST($1 AS DATE), $2)".to_owned(),
+                vec!["2024-01-02".to_owned(), "6".to_owned()]
+            )
+        );
+    }
+
+    #[test]
+    fn expands_mixed_directions() {
+        let columns = [
+            CursorColumn {
+                direction: SortDirection::Desc,
+                ..column("title", Some("Diesel"))
+            },
+            column("id", Some("6")),
+        ];
+        assert_eq!(
+            render(&columns),
+            (
+                "(title < $1) OR (title = $2 AND id > $3)".to_owned(),
+                vec!["Diesel".to_owned(), "Diesel".to_owned(), "6".to_owned()]
+            )
+        );
+    }
+
+    #[test]
+    fn sorts_the_nulls_like_the_order() {
+        let nullable = |value, nulls| CursorColumn {
+            nulls: Some(nulls),
+            ..column("score", value)
+        };
+        let sql = |columns: &[CursorColumn<'_>]| render(columns).0;
+        let id = column("id", Some("6"));
+        assert_eq!(
+            sql(&[nullable(Some("1"), NullsOrder::Last), id]),
+            "((score > $1 OR score IS NULL)) OR (score = $2 AND id > $3)"
+        );
+        assert_eq!(
+            sql(&[nullable(Some("1"), NullsOrder::First), id]),
+            "(score > $1) OR (score = $2 AND id > $3)"
+        );
+        assert_eq!(
+            sql(&[nullable(None, NullsOrder::Last), id]),
+            "(score IS NULL AND id > $1)"
+        );
+        assert_eq!(
+            sql(&[nullable(None, NullsOrder::First), id]),
+            "(score IS NOT NULL) OR (score IS NULL AND id > $1)"
+        );
+    }
+
+    #[test]
+    fn coalesces_joined_columns() {
+        let joined = |nulls| CursorColumn {
+            cursor: ("(SELECT name FROM users WHERE id = ", ")"),
+            nulls: Some(nulls),
+            joined: true,
+            ..column("(SELECT name FROM users WHERE id = user_id)", Some("3"))
+        };
+        let id = column("id", Some("6"));
+        assert_eq!(
+            render(&[joined(NullsOrder::Last), id]).0,
+            "(COALESCE((SELECT name FROM users WHERE id = user_id) > (SELECT name FROM users WHERE id = $1), \
+             (SELECT name FROM users WHERE id = $2) IS NOT NULL)) \
+             OR ((SELECT name FROM users WHERE id = user_id) IS NOT DISTINCT FROM (SELECT name FROM users WHERE id = $3) AND id > $4)"
+        );
+        assert_eq!(
+            render(&[joined(NullsOrder::First)]).0,
+            "(COALESCE((SELECT name FROM users WHERE id = user_id) > (SELECT name FROM users WHERE id = $1), \
+             (SELECT name FROM users WHERE id = $2) IS NULL AND (SELECT name FROM users WHERE id = user_id) IS NOT NULL))"
+        );
+    }
+
+    #[test]
+    fn empty_orders_are_all_or_nothing() {
+        assert_eq!(render(&[]), ("TRUE".to_owned(), vec![]));
+        let null = CursorColumn {
+            nulls: Some(NullsOrder::Last),
+            ..column("score", None)
+        };
+        assert_eq!(render(&[null]), ("FALSE".to_owned(), vec![]));
+    }
+}
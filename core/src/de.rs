@@ -0,0 +1,175 @@
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use std::{fmt, marker::PhantomData, str::FromStr};
+
+/// Deserializes the value of a `multiple` filter from either a single value
+/// (`?id=1`), a bracketed sequence (`?id[]=1&id[]=2`) or an indexed map
+/// (`?id[0]=1&id[1]=2`), which is how `serde_qs` hands them over. In JSON
+/// bodies, a bare number or boolean is a single value and `null` no value.
+pub fn one_or_many<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr,
+    T::Err: fmt::Display,
+{
+    deserializer.deserialize_any(OneOrMany(PhantomData))
+}
+
+/// Deserializes the value of a `multiple` filter from a single delimited
+/// string, e.g `?status=open,closed`. Empty values are skipped. A sequence is
+/// accepted as well, which is how the filters are serialized.
+pub fn delimited<'de, D, T>(deserializer: D, delimiter: &str) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr,
+    T::Err: fmt::Display,
+{
+    deserializer.deserialize_any(Delimited(delimiter, PhantomData))
+}
+
+struct Delimited<'a, T>(&'a str, PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for Delimited<'_, T>
+where
+    T: Deserialize<'de> + FromStr,
+    T::Err: fmt::Display,
+{
+    type Value = Option<Vec<T>>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a delimited string or a sequence of values")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        let values = value
+            .split(self.0)
+            .filter(|v| !v.is_empty())
+            .map(|v| v.parse().map_err(E::custom))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match values.is_empty() {
+            true => Ok(None),
+            false => Ok(Some(values)),
+        }
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        OneOrMany(PhantomData).visit_seq(seq)
+    }
+}
+
+struct OneOrMany<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for OneOrMany<T>
+where
+    T: Deserialize<'de> + FromStr,
+    T::Err: fmt::Display,
+{
+    type Value = Option<Vec<T>>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a value or a sequence of values")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        value.parse().map(|v| Some(vec![v])).map_err(E::custom)
+    }
+
+    // Numbers and booleans are parsed like the strings of query strings
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        self.visit_str(&value.to_string())
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = vec![];
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Some(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut values = vec![];
+        while let Some((index, value)) = map.next_entry::<String, T>()? {
+            let index: usize = index.parse().map_err(de::Error::custom)?;
+            values.push((index, value));
+        }
+        values.sort_by_key(|(index, _)| *index);
+        Ok(Some(values.into_iter().map(|(_, value)| value).collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::value::{
+        BoolDeserializer, Error, F64Deserializer, I64Deserializer, SeqDeserializer,
+        StrDeserializer, UnitDeserializer,
+    };
+
+    #[test]
+    fn one_or_many_takes_a_value_or_a_sequence() {
+        let one: Option<Vec<i32>> = one_or_many(StrDeserializer::<Error>::new("1")).unwrap();
+        assert_eq!(one, Some(vec![1]));
+        let many: Option<Vec<i32>> =
+            one_or_many(SeqDeserializer::<_, Error>::new(vec![1, 2].into_iter())).unwrap();
+        assert_eq!(many, Some(vec![1, 2]));
+        let none: Option<Vec<i32>> = one_or_many(SeqDeserializer::<_, Error>::new(
+            Vec::<i32>::new().into_iter(),
+        ))
+        .unwrap();
+        assert_eq!(none, Some(vec![]));
+    }
+
+    #[test]
+    fn one_or_many_takes_numbers_and_null() {
+        let number: Option<Vec<i32>> = one_or_many(I64Deserializer::<Error>::new(7)).unwrap();
+        assert_eq!(number, Some(vec![7]));
+        let text: Option<Vec<String>> = one_or_many(I64Deserializer::<Error>::new(7)).unwrap();
+        assert_eq!(text, Some(vec!["7".to_owned()]));
+        let float: Option<Vec<f64>> = one_or_many(F64Deserializer::<Error>::new(1.5)).unwrap();
+        assert_eq!(float, Some(vec![1.5]));
+        let flag: Option<Vec<bool>> = one_or_many(BoolDeserializer::<Error>::new(true)).unwrap();
+        assert_eq!(flag, Some(vec![true]));
+        let null: Option<Vec<i32>> = one_or_many(UnitDeserializer::<Error>::new()).unwrap();
+        assert_eq!(null, None);
+    }
+
+    #[test]
+    fn one_or_many_rejects_malformed_values() {
+        assert!(one_or_many::<_, i32>(StrDeserializer::<Error>::new("x")).is_err());
+        assert!(one_or_many::<_, u8>(I64Deserializer::<Error>::new(-1)).is_err());
+    }
+
+    #[test]
+    fn delimited_splits_the_string() {
+        let values: Option<Vec<String>> =
+            delimited(StrDeserializer::<Error>::new("open,,closed"), ",").unwrap();
+        assert_eq!(values, Some(vec!["open".to_owned(), "closed".to_owned()]));
+        let empty: Option<Vec<String>> = delimited(StrDeserializer::<Error>::new(""), ",").unwrap();
+        assert_eq!(empty, None);
+    }
+}
@@ -0,0 +1,22 @@
+//! Filters configured per deployment, constraining every query of a model
+//! whatever the request, e.g to the region of the instance.
+
+/// The default filters of a model, implemented on its filters struct and read at
+/// runtime, e.g from the configuration loaded at startup. With
+/// `#[diesel_filter(default_filters)]`, `with_default_filters` sets the parameters
+/// a request leaves unset to them, the ones it sets win.
+///
+/// ```text
+/// impl DefaultFilters for ProjectFilters {
+///     fn default_filters() -> Option<Self> {
+///         let region = CONFIG.region.clone()?;
+///         Some(ProjectFilters { region: Some(region), name: None, status: None })
+///     }
+/// }
+///
+/// let projects = Project::filtered(&filters.with_default_filters(), conn)?;
+/// ```
+pub trait DefaultFilters: Sized {
+    /// The defaults, `None` when the deployment has none
+    fn default_filters() -> Option<Self>;
+}
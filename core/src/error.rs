@@ -0,0 +1,93 @@
+use std::fmt;
+
+/// Error returned by the generated `filtered` method when the filters are
+/// validated before running the query. The invalid filters, to answer with a 400,
+/// are told apart from the failures of the database, to answer with a 500.
+#[derive(Debug)]
+pub enum Error {
+    #[cfg(feature = "validation")]
+    Validation(validator::ValidationErrors),
+    /// Holds the `garde::Report` of the failed validation
+    #[cfg(feature = "garde")]
+    Garde(Box<dyn std::error::Error + Send + Sync>),
+    /// The page or the number of rows per page, defaults applied, is below 1
+    #[cfg(feature = "pagination")]
+    Pagination {
+        page: i64,
+        per_page: i64,
+    },
+    /// A `sort` term with an unknown key or direction
+    Sort(crate::sort::SortError),
+    /// More values than the `max_values` of a `multiple` filter
+    TooManyValues {
+        filter: &'static str,
+        values: usize,
+        max: usize,
+    },
+    Database(diesel::result::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "validation")]
+            Error::Validation(err) => write!(f, "invalid filters: {}", err),
+            #[cfg(feature = "garde")]
+            Error::Garde(err) => write!(f, "invalid filters: {}", err),
+            #[cfg(feature = "pagination")]
+            Error::Pagination { page, per_page } => write!(
+                f,
+                "invalid pagination: page {} of {} rows, both start at 1",
+                page, per_page
+            ),
+            Error::Sort(err) => err.fmt(f),
+            Error::TooManyValues {
+                filter,
+                values,
+                max,
+            } => write!(
+                f,
+                "invalid filters: {} values for `{}`, at most {}",
+                values, filter, max
+            ),
+            Error::Database(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "validation")]
+            Error::Validation(err) => Some(err),
+            #[cfg(feature = "garde")]
+            Error::Garde(err) => Some(err.as_ref()),
+            #[cfg(feature = "pagination")]
+            Error::Pagination { .. } => None,
+            Error::Sort(err) => Some(err),
+            Error::TooManyValues { .. } => None,
+            Error::Database(err) => Some(err),
+        }
+    }
+}
+
+#[cfg(feature = "validation")]
+impl From<validator::ValidationErrors> for Error {
+    fn from(err: validator::ValidationErrors) -> Self {
+        Error::Validation(err)
+    }
+}
+
+impl Error {
+    /// Whether the filters are at fault rather than the database, e.g to answer
+    /// with a 400 instead of a 500
+    pub fn is_invalid_filters(&self) -> bool {
+        !matches!(self, Error::Database(_))
+    }
+}
+
+impl From<diesel::result::Error> for Error {
+    fn from(err: diesel::result::Error) -> Self {
+        Error::Database(err)
+    }
+}
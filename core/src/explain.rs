@@ -0,0 +1,45 @@
+//! The query plans of the filters, to check the indexes used by specific filter
+//! combinations.
+use diesel::{pg::Pg, prelude::*, query_builder::*, sql_types::Text};
+
+/// `EXPLAIN (ANALYZE, BUFFERS)` of `query`, loading the lines of the plan.
+/// The query is run by Postgres to time it.
+#[derive(Debug, Clone, Copy)]
+pub struct Explain<T> {
+    query: T,
+}
+
+impl<T> Explain<T> {
+    pub fn new(query: T) -> Self {
+        Explain { query }
+    }
+
+    /// The plan, one line per node
+    pub fn plan(self, conn: &mut PgConnection) -> QueryResult<String>
+    where
+        T: QueryFragment<Pg>,
+    {
+        Ok(self.load::<String>(conn)?.join("\n"))
+    }
+}
+
+impl<T> QueryId for Explain<T> {
+    type QueryId = ();
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<T> Query for Explain<T> {
+    type SqlType = Text;
+}
+
+impl<T> RunQueryDsl<PgConnection> for Explain<T> {}
+
+impl<T> QueryFragment<Pg> for Explain<T>
+where
+    T: QueryFragment<Pg>,
+{
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        out.push_sql("EXPLAIN (ANALYZE, BUFFERS) ");
+        self.query.walk_ast(out.reborrow())
+    }
+}
@@ -0,0 +1,52 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A boolean tree of conditions on the filterable fields of a model, `F` is
+/// the `<Model>Filter` enum generated by the derive.
+///
+/// `And` of nothing matches every row and `Or` of nothing matches none.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FilterExpr<F> {
+    And(Vec<FilterExpr<F>>),
+    Or(Vec<FilterExpr<F>>),
+    Not(Box<FilterExpr<F>>),
+    Filter(F),
+}
+
+impl<F> FilterExpr<F> {
+    pub fn and(self, other: Self) -> Self {
+        match self {
+            FilterExpr::And(mut exprs) => {
+                exprs.push(other);
+                FilterExpr::And(exprs)
+            }
+            expr => FilterExpr::And(vec![expr, other]),
+        }
+    }
+
+    pub fn or(self, other: Self) -> Self {
+        match self {
+            FilterExpr::Or(mut exprs) => {
+                exprs.push(other);
+                FilterExpr::Or(exprs)
+            }
+            expr => FilterExpr::Or(vec![expr, other]),
+        }
+    }
+}
+
+impl<F> std::ops::Not for FilterExpr<F> {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        FilterExpr::Not(Box::new(self))
+    }
+}
+
+impl<F> From<F> for FilterExpr<F> {
+    fn from(filter: F) -> Self {
+        FilterExpr::Filter(filter)
+    }
+}
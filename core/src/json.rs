@@ -0,0 +1,224 @@
+//! A JSON filter DSL for POST based search endpoints.
+//!
+//! ```json
+//! { "name": { "ilike": "foo" }, "category": { "in": ["books", "games"] }, "page": 2 }
+//! ```
+//!
+//! Each field takes the operator matching its filter kind: `eq` for basic
+//! filters (or `in` for `multiple` ones), `like` for `substring` filters and
+//! `ilike` for `insensitive` ones. A bare value is a shorthand for the
+//! operator of the field, `null` leaves the filter unset.
+use serde_json::Map;
+pub use serde_json::Value;
+use std::{fmt, str::FromStr};
+
+/// The condition on a single field of the payload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub field: String,
+    pub operator: Option<String>,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonFilterError {
+    NotAnObject,
+    UnknownField(String),
+    /// The condition object of a field doesn't have exactly one operator
+    InvalidCondition(String),
+    UnsupportedOperator {
+        field: String,
+        operator: String,
+    },
+    InvalidValue {
+        field: String,
+        value: Value,
+    },
+}
+
+impl fmt::Display for JsonFilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonFilterError::NotAnObject => f.write_str("filters must be a JSON object"),
+            JsonFilterError::UnknownField(field) => write!(f, "unknown field `{}`", field),
+            JsonFilterError::InvalidCondition(field) => {
+                write!(
+                    f,
+                    "the condition on `{}` must have a single operator",
+                    field
+                )
+            }
+            JsonFilterError::UnsupportedOperator { field, operator } => write!(
+                f,
+                "operator `{}` is not supported for `{}`",
+                operator, field
+            ),
+            JsonFilterError::InvalidValue { field, value } => {
+                write!(f, "invalid value `{}` for `{}`", value, field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for JsonFilterError {}
+
+/// Splits the payload into the conditions on each of its fields.
+pub fn conditions(value: Value) -> Result<Vec<Condition>, JsonFilterError> {
+    let fields = match value {
+        Value::Object(fields) => fields,
+        _ => return Err(JsonFilterError::NotAnObject),
+    };
+
+    // `null` leaves the filter unset
+    fields
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(field, value)| match value {
+            Value::Object(condition) => {
+                let (operator, value) = single(condition)
+                    .ok_or_else(|| JsonFilterError::InvalidCondition(field.clone()))?;
+                Ok(Condition {
+                    field,
+                    operator: Some(operator),
+                    value,
+                })
+            }
+            value => Ok(Condition {
+                field,
+                operator: None,
+                value,
+            }),
+        })
+        .collect()
+}
+
+fn single(condition: Map<String, Value>) -> Option<(String, Value)> {
+    match condition.len() {
+        1 => condition.into_iter().next(),
+        _ => None,
+    }
+}
+
+impl Condition {
+    /// The value of a condition made with `operator`.
+    pub fn value<T: FromStr>(&self, operator: &str) -> Result<T, JsonFilterError> {
+        self.check(operator, false)?;
+        self.parse(&self.value)
+    }
+
+    /// The values of a condition made with `operator`, or with `in` when
+    /// `operator` is `eq`. A single value is accepted as well as an array.
+    pub fn values<T: FromStr>(&self, operator: &str) -> Result<Vec<T>, JsonFilterError> {
+        self.check(operator, operator == "eq")?;
+        match &self.value {
+            Value::Array(values) => values.iter().map(|value| self.parse(value)).collect(),
+            value => Ok(vec![self.parse(value)?]),
+        }
+    }
+
+    fn check(&self, operator: &str, allow_in: bool) -> Result<(), JsonFilterError> {
+        match self.operator.as_deref() {
+            None => Ok(()),
+            Some(op) if op == operator || (allow_in && op == "in") => Ok(()),
+            Some(op) => Err(JsonFilterError::UnsupportedOperator {
+                field: self.field.clone(),
+                operator: op.to_owned(),
+            }),
+        }
+    }
+
+    fn parse<T: FromStr>(&self, value: &Value) -> Result<T, JsonFilterError> {
+        let parsed = match value {
+            Value::String(value) => value.parse().ok(),
+            Value::Number(value) => value.to_string().parse().ok(),
+            Value::Bool(value) => value.to_string().parse().ok(),
+            _ => None,
+        };
+        parsed.ok_or_else(|| JsonFilterError::InvalidValue {
+            field: self.field.clone(),
+            value: value.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn splits_the_payload_into_conditions() {
+        let conditions = conditions(json!({
+            "name": { "ilike": "foo" },
+            "category": ["books", "games"],
+            "owner": null,
+        }))
+        .unwrap();
+        assert_eq!(
+            conditions,
+            vec![
+                Condition {
+                    field: "category".to_owned(),
+                    operator: None,
+                    value: json!(["books", "games"]),
+                },
+                Condition {
+                    field: "name".to_owned(),
+                    operator: Some("ilike".to_owned()),
+                    value: json!("foo"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_payload_has_no_conditions() {
+        assert_eq!(conditions(json!({})).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn rejects_malformed_payloads() {
+        assert_eq!(conditions(json!([1])), Err(JsonFilterError::NotAnObject));
+        assert_eq!(conditions(json!("name")), Err(JsonFilterError::NotAnObject));
+        assert_eq!(
+            conditions(json!({ "name": { "eq": "a", "like": "b" } })),
+            Err(JsonFilterError::InvalidCondition("name".to_owned()))
+        );
+        assert_eq!(
+            conditions(json!({ "name": {} })),
+            Err(JsonFilterError::InvalidCondition("name".to_owned()))
+        );
+    }
+
+    #[test]
+    fn conditions_convert_their_values() {
+        let condition = |operator: Option<&str>, value: Value| Condition {
+            field: "id".to_owned(),
+            operator: operator.map(str::to_owned),
+            value,
+        };
+        assert_eq!(condition(None, json!(1)).value::<i32>("eq"), Ok(1));
+        assert_eq!(condition(Some("eq"), json!("1")).value::<i32>("eq"), Ok(1));
+        assert_eq!(condition(None, json!(true)).value::<bool>("eq"), Ok(true));
+        assert_eq!(
+            condition(Some("in"), json!([1, 2])).values::<i32>("eq"),
+            Ok(vec![1, 2])
+        );
+        assert_eq!(condition(None, json!(3)).values::<i32>("eq"), Ok(vec![3]));
+        assert_eq!(
+            condition(Some("in"), json!([1])).value::<i32>("eq"),
+            Err(JsonFilterError::UnsupportedOperator {
+                field: "id".to_owned(),
+                operator: "in".to_owned(),
+            })
+        );
+        assert_eq!(
+            condition(None, json!({ "nested": 1 })).value::<i32>("eq"),
+            Err(JsonFilterError::InvalidValue {
+                field: "id".to_owned(),
+                value: json!({ "nested": 1 }),
+            })
+        );
+        assert!(condition(None, json!("x")).value::<i32>("eq").is_err());
+    }
+}
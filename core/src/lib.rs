@@ -1,8 +1,72 @@
-#[cfg(feature = "pagination")]
+#[cfg(any(
+    feature = "explain",
+    feature = "pagination",
+    feature = "replica",
+    feature = "union",
+    feature = "validation",
+    feature = "garde"
+))]
 extern crate diesel;
 
 pub use diesel_filter_query::*;
 #[cfg(feature = "pagination")]
 pub mod pagination;
+pub mod sql;
 #[cfg(feature = "pagination")]
 pub use pagination::*;
+#[cfg(feature = "axum")]
+pub mod rejection;
+#[cfg(feature = "axum")]
+pub use rejection::*;
+#[cfg(feature = "cache")]
+pub mod cache;
+#[cfg(feature = "cache")]
+pub use cache::FilterCache;
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "tonic")]
+pub mod proto;
+#[cfg(feature = "tonic")]
+pub use proto::*;
+#[cfg(any(feature = "validation", feature = "garde", feature = "pagination"))]
+pub mod error;
+#[cfg(feature = "expr")]
+pub mod expr;
+#[cfg(feature = "json")]
+pub mod json;
+// The `log` crate of the `log_sql` logs, without a dependency of the user on it
+#[cfg(feature = "expr")]
+pub use expr::FilterExpr;
+#[cfg(feature = "log")]
+#[doc(hidden)]
+pub use log as __log;
+#[cfg(feature = "explain")]
+pub mod explain;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "odata")]
+pub mod odata;
+pub mod one_of;
+pub mod op;
+pub mod prelude;
+#[cfg(feature = "replica")]
+pub mod replica;
+#[cfg(feature = "replica")]
+pub use replica::ReadConnection;
+pub mod cursor;
+pub mod defaults;
+pub use defaults::DefaultFilters;
+#[cfg(feature = "rsql")]
+pub mod rsql;
+#[cfg(feature = "saved_search")]
+pub mod saved_search;
+#[cfg(feature = "search_syntax")]
+pub mod search;
+pub mod sort;
+#[cfg(feature = "union")]
+pub mod union;
+#[cfg(any(feature = "validation", feature = "garde", feature = "pagination"))]
+pub use error::Error;
+#[cfg(feature = "metrics")]
+pub use metrics::{FilterEvent, FilterMetrics};
+pub use sort::SortDirection;
@@ -0,0 +1,58 @@
+use std::{sync::OnceLock, time::Duration};
+
+/// A call of a generated `filtered` method.
+#[derive(Debug, Clone, Copy)]
+pub struct FilterEvent {
+    pub table: &'static str,
+    /// Fields of the filters struct, in declaration order
+    pub fields: &'static [&'static str],
+    /// Bit `i` is set when `fields[i]` is set, fields after the 64th are not tracked
+    pub active: u64,
+    pub elapsed: Duration,
+}
+
+impl FilterEvent {
+    /// Names of the fields that were set.
+    pub fn active_fields(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.fields
+            .iter()
+            .take(64)
+            .enumerate()
+            .filter(move |(i, _)| self.active & (1 << i) != 0)
+            .map(|(_, field)| *field)
+    }
+}
+
+/// Receives an event for every call of a generated `filtered` method, e.g to
+/// record a histogram per table and combination of filters.
+pub trait FilterMetrics: Send + Sync {
+    fn record(&self, event: &FilterEvent);
+}
+
+impl<F> FilterMetrics for F
+where
+    F: Fn(&FilterEvent) + Send + Sync,
+{
+    fn record(&self, event: &FilterEvent) {
+        self(event)
+    }
+}
+
+static METRICS: OnceLock<Box<dyn FilterMetrics>> = OnceLock::new();
+
+/// Installs the metrics hook, it can only be set once.
+pub fn set_metrics<M: FilterMetrics + 'static>(metrics: M) -> Result<(), M> {
+    let mut metrics = Some(metrics);
+    METRICS.get_or_init(|| Box::new(metrics.take().unwrap()));
+    match metrics {
+        Some(metrics) => Err(metrics),
+        None => Ok(()),
+    }
+}
+
+#[doc(hidden)]
+pub fn record(event: FilterEvent) {
+    if let Some(metrics) = METRICS.get() {
+        metrics.record(&event);
+    }
+}
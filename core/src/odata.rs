@@ -0,0 +1,578 @@
+//! Maps the common [OData](https://www.odata.org/getting-started/basic-tutorial/#queryData)
+//! query options onto the generated filters.
+//!
+//! `$filter` supports a conjunction (`and`) of `eq`, `in`, `contains`,
+//! `startswith`, `ge`, `le` and `has` comparisons: `eq` and `in` for basic
+//! filters, `contains` for `substring` filters, `startswith` for `prefix`
+//! filters, `ge`/`le` for `gte`/`lte` filters and `has` for `has_flags` filters. `ne`, `gt` and `lt` are parsed but have no
+//! equivalent in the filters and are rejected. `$top` and `$skip` are mapped
+//! onto `page` and `per_page`, `$orderby` onto `sort` for the models with
+//! `#[sortable]` fields and rejected otherwise.
+use std::{fmt, str::FromStr};
+
+/// The OData system query options of a request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ODataQuery {
+    pub filter: Option<String>,
+    pub orderby: Option<String>,
+    pub top: Option<i64>,
+    pub skip: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    In,
+    Contains,
+    StartsWith,
+    Has,
+}
+
+/// A single comparison of `$filter`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    pub property: String,
+    pub operator: Operator,
+    pub values: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ODataError {
+    /// `$filter` is not a valid expression, `position` is a byte offset
+    Syntax {
+        position: usize,
+        message: &'static str,
+    },
+    /// A query option or construct that has no equivalent in the filters
+    Unsupported(&'static str),
+    UnknownProperty(String),
+    UnsupportedOperator {
+        property: String,
+        operator: Operator,
+    },
+    InvalidValue {
+        property: String,
+        value: String,
+    },
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = match self {
+            Operator::Eq => "eq",
+            Operator::Ne => "ne",
+            Operator::Gt => "gt",
+            Operator::Ge => "ge",
+            Operator::Lt => "lt",
+            Operator::Le => "le",
+            Operator::In => "in",
+            Operator::Contains => "contains",
+            Operator::StartsWith => "startswith",
+            Operator::Has => "has",
+        };
+        f.write_str(operator)
+    }
+}
+
+impl fmt::Display for ODataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ODataError::Syntax { position, message } => {
+                write!(f, "invalid $filter at {}: {}", position, message)
+            }
+            ODataError::Unsupported(what) => write!(f, "{} is not supported", what),
+            ODataError::UnknownProperty(property) => write!(f, "unknown property `{}`", property),
+            ODataError::UnsupportedOperator { property, operator } => write!(
+                f,
+                "operator `{}` is not supported for `{}`",
+                operator, property
+            ),
+            ODataError::InvalidValue { property, value } => {
+                write!(f, "invalid value `{}` for `{}`", value, property)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ODataError {}
+
+impl ODataQuery {
+    /// Reads the `$`-prefixed options of a query string, other parameters
+    /// are ignored.
+    pub fn from_query(query: &str) -> Result<Self, ODataError> {
+        let mut options = Self::default();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = match pair.find('=') {
+                Some(i) => (&pair[..i], &pair[i + 1..]),
+                None => (pair, ""),
+            };
+            let value = percent_decode(value);
+            match percent_decode(key).as_str() {
+                "$filter" => options.filter = Some(value),
+                "$orderby" => options.orderby = Some(value),
+                "$top" => options.top = Some(parse_count("$top", value)?),
+                "$skip" => options.skip = Some(parse_count("$skip", value)?),
+                _ => {}
+            }
+        }
+        Ok(options)
+    }
+
+    /// The comparisons of `$filter`.
+    pub fn comparisons(&self) -> Result<Vec<Comparison>, ODataError> {
+        match &self.filter {
+            Some(filter) => parse_filter(filter),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// `$top` and `$skip` as `(page, per_page)`, `$skip` has to be a multiple
+    /// of `$top`.
+    pub fn pagination(&self) -> Result<(Option<i64>, Option<i64>), ODataError> {
+        match (self.top, self.skip) {
+            (top, None) => Ok((None, top)),
+            (Some(top), Some(skip)) if top > 0 && skip % top == 0 => {
+                Ok((Some(skip / top + 1), Some(top)))
+            }
+            (Some(_), Some(_)) => Err(ODataError::Unsupported(
+                "$skip that is not a multiple of $top",
+            )),
+            (None, Some(_)) => Err(ODataError::Unsupported("$skip without $top")),
+        }
+    }
+
+    /// `$orderby` as a `sort` parameter, `author/name desc,title` is sorted by
+    /// `author.name:desc,title`.
+    pub fn sort(&self) -> Option<String> {
+        let orderby = self.orderby.as_deref()?;
+        let terms = orderby
+            .split(',')
+            .map(|term| {
+                let mut words = term.split_whitespace();
+                let key = words.next().unwrap_or_default().replace('/', ".");
+                match words.next() {
+                    Some(direction) => format!("{}:{}", key, direction),
+                    None => key,
+                }
+            })
+            .collect::<Vec<_>>();
+        Some(terms.join(","))
+    }
+}
+
+impl Comparison {
+    /// The value of a comparison made with `operator`.
+    pub fn value<T: FromStr>(&self, operator: Operator) -> Result<T, ODataError> {
+        match self.values.as_slice() {
+            [value] if self.operator == operator => self.parse(value),
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    /// The values of a comparison made with `operator`, or with `in` when
+    /// `operator` is `eq`.
+    pub fn values<T: FromStr>(&self, operator: Operator) -> Result<Vec<T>, ODataError> {
+        match self.operator {
+            Operator::In if operator == Operator::Eq => {
+                self.values.iter().map(|value| self.parse(value)).collect()
+            }
+            _ => Ok(vec![self.value(operator)?]),
+        }
+    }
+
+    fn parse<T: FromStr>(&self, value: &str) -> Result<T, ODataError> {
+        value.parse().map_err(|_| ODataError::InvalidValue {
+            property: self.property.clone(),
+            value: value.to_owned(),
+        })
+    }
+
+    fn unsupported(&self) -> ODataError {
+        ODataError::UnsupportedOperator {
+            property: self.property.clone(),
+            operator: self.operator,
+        }
+    }
+}
+
+/// Parses a `$filter` expression into the list of its `and`-ed comparisons.
+pub fn parse_filter(filter: &str) -> Result<Vec<Comparison>, ODataError> {
+    let tokens = tokenize(filter)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        index: 0,
+        end: filter.len(),
+    };
+    let mut comparisons = vec![];
+    parser.conjunction(&mut comparisons)?;
+    match parser.next() {
+        None => Ok(comparisons),
+        Some((Token::Word(word), _)) if word == "or" => Err(ODataError::Unsupported("`or`")),
+        Some((_, position)) => Err(ODataError::Syntax {
+            position,
+            message: "expected `and`",
+        }),
+    }
+}
+
+fn parse_count(option: &'static str, value: String) -> Result<i64, ODataError> {
+    match value.parse() {
+        Ok(count) if count >= 0 => Ok(count),
+        _ => Err(ODataError::InvalidValue {
+            property: option.to_owned(),
+            value,
+        }),
+    }
+}
+
+fn percent_decode(input: &str) -> String {
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut iter = input.bytes();
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = iter.clone().take(2).collect::<Vec<_>>();
+                match std::str::from_utf8(&hex)
+                    .ok()
+                    .filter(|hex| hex.len() == 2)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(decoded) => {
+                        bytes.push(decoded);
+                        iter.nth(1);
+                    }
+                    None => bytes.push(byte),
+                }
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    /// Property names, keywords, numbers, GUIDs, dates...
+    Word(String),
+    /// A single-quoted string literal
+    Str(String),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ODataError> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            '\'' => {
+                // quotes are escaped by doubling them
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\'')) if chars.peek().map(|(_, c)| *c) == Some('\'') => {
+                            chars.next();
+                            value.push('\'');
+                        }
+                        Some((_, '\'')) => break,
+                        Some((_, c)) => value.push(c),
+                        None => {
+                            return Err(ODataError::Syntax {
+                                position,
+                                message: "unterminated string",
+                            })
+                        }
+                    }
+                }
+                Token::Str(value)
+            }
+            c if is_word_char(c) => {
+                let mut word = c.to_string();
+                while let Some((_, c)) = chars.peek().filter(|(_, c)| is_word_char(*c)) {
+                    word.push(*c);
+                    chars.next();
+                }
+                Token::Word(word)
+            }
+            _ => {
+                return Err(ODataError::Syntax {
+                    position,
+                    message: "unexpected character",
+                })
+            }
+        };
+        tokens.push((token, position));
+    }
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | ':' | '+' | '/')
+}
+
+struct Parser<'a> {
+    tokens: &'a [(Token, usize)],
+    index: usize,
+    end: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<(&'a Token, usize)> {
+        let token = self.tokens.get(self.index)?;
+        self.index += 1;
+        Some((&token.0, token.1))
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map(|(_, position)| *position)
+            .unwrap_or(self.end)
+    }
+
+    fn expect(&mut self, expected: Token, message: &'static str) -> Result<(), ODataError> {
+        let position = self.position();
+        match self.next() {
+            Some((token, _)) if *token == expected => Ok(()),
+            _ => Err(ODataError::Syntax { position, message }),
+        }
+    }
+
+    fn word(&mut self, message: &'static str) -> Result<&'a str, ODataError> {
+        let position = self.position();
+        match self.next() {
+            Some((Token::Word(word), _)) => Ok(word),
+            _ => Err(ODataError::Syntax { position, message }),
+        }
+    }
+
+    fn literal(&mut self) -> Result<String, ODataError> {
+        let position = self.position();
+        match self.next() {
+            Some((Token::Word(word), _)) | Some((Token::Str(word), _)) => Ok(word.clone()),
+            _ => Err(ODataError::Syntax {
+                position,
+                message: "expected a value",
+            }),
+        }
+    }
+
+    fn conjunction(&mut self, comparisons: &mut Vec<Comparison>) -> Result<(), ODataError> {
+        loop {
+            self.term(comparisons)?;
+            match self.peek() {
+                Some(Token::Word(word)) if word == "and" => self.index += 1,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn term(&mut self, comparisons: &mut Vec<Comparison>) -> Result<(), ODataError> {
+        if self.peek() == Some(&Token::Open) {
+            // `and` is associative, so a group of conjunctions is flattened
+            self.index += 1;
+            self.conjunction(comparisons)?;
+            if let Some(Token::Word(word)) = self.peek() {
+                if word == "or" {
+                    return Err(ODataError::Unsupported("`or`"));
+                }
+            }
+            return self.expect(Token::Close, "expected `)`");
+        }
+
+        let word = self.word("expected a property")?;
+        match word {
+            "not" => return Err(ODataError::Unsupported("`not`")),
+            "contains" | "startswith" if self.peek() == Some(&Token::Open) => {
+                let operator = match word {
+                    "contains" => Operator::Contains,
+                    _ => Operator::StartsWith,
+                };
+                self.index += 1;
+                let property = self.word("expected a property")?.to_owned();
+                self.expect(Token::Comma, "expected `,`")?;
+                let value = self.literal()?;
+                self.expect(Token::Close, "expected `)`")?;
+                comparisons.push(Comparison {
+                    property,
+                    operator,
+                    values: vec![value],
+                });
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let property = word.to_owned();
+        let position = self.position();
+        let operator = match self.word("expected an operator")? {
+            "eq" => Operator::Eq,
+            "ne" => Operator::Ne,
+            "gt" => Operator::Gt,
+            "ge" => Operator::Ge,
+            "lt" => Operator::Lt,
+            "le" => Operator::Le,
+            "in" => Operator::In,
+            "has" => Operator::Has,
+            _ => {
+                return Err(ODataError::Syntax {
+                    position,
+                    message: "expected an operator",
+                })
+            }
+        };
+        let values = match operator {
+            Operator::In => {
+                self.expect(Token::Open, "expected `(`")?;
+                let mut values = vec![self.literal()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.index += 1;
+                    values.push(self.literal()?);
+                }
+                self.expect(Token::Close, "expected `)`")?;
+                values
+            }
+            _ => vec![self.literal()?],
+        };
+        comparisons.push(Comparison {
+            property,
+            operator,
+            values,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comparison(property: &str, operator: Operator, values: &[&str]) -> Comparison {
+        Comparison {
+            property: property.to_owned(),
+            operator,
+            values: values.iter().map(|v| (*v).to_owned()).collect(),
+        }
+    }
+
+    #[test]
+    fn parses_a_conjunction_of_comparisons() {
+        assert_eq!(
+            parse_filter("name eq 'foo' and (status in ('open', 'closed') and year ge 2000) and contains(title,'rust')").unwrap(),
+            vec![
+                comparison("name", Operator::Eq, &["foo"]),
+                comparison("status", Operator::In, &["open", "closed"]),
+                comparison("year", Operator::Ge, &["2000"]),
+                comparison("title", Operator::Contains, &["rust"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn unescapes_doubled_quotes() {
+        assert_eq!(
+            parse_filter("author eq 'O''Brien'").unwrap(),
+            vec![comparison("author", Operator::Eq, &["O'Brien"])]
+        );
+    }
+
+    #[test]
+    fn empty_query_has_no_options() {
+        let query = ODataQuery::from_query("").unwrap();
+        assert_eq!(query, ODataQuery::default());
+        assert_eq!(query.comparisons().unwrap(), vec![]);
+        assert_eq!(query.pagination().unwrap(), (None, None));
+        assert_eq!(query.sort(), None);
+    }
+
+    #[test]
+    fn rejects_malformed_filters() {
+        assert!(matches!(
+            parse_filter("name eq 'foo"),
+            Err(ODataError::Syntax { position: 8, .. })
+        ));
+        assert!(matches!(
+            parse_filter("name foo 'bar'"),
+            Err(ODataError::Syntax { .. })
+        ));
+        assert!(matches!(
+            parse_filter("name eq"),
+            Err(ODataError::Syntax { .. })
+        ));
+        assert!(matches!(
+            parse_filter("id in (1, 2"),
+            Err(ODataError::Syntax { .. })
+        ));
+        assert!(matches!(
+            parse_filter("name eq 'a' name eq 'b'"),
+            Err(ODataError::Syntax { .. })
+        ));
+        assert!(matches!(
+            parse_filter("name eq 'a' ; "),
+            Err(ODataError::Syntax { .. })
+        ));
+        assert_eq!(
+            parse_filter("a eq 1 or b eq 2"),
+            Err(ODataError::Unsupported("`or`"))
+        );
+        assert_eq!(
+            parse_filter("not a eq 1"),
+            Err(ODataError::Unsupported("`not`"))
+        );
+    }
+
+    #[test]
+    fn reads_the_options_of_a_query_string() {
+        let query = ODataQuery::from_query(
+            "$filter=name%20eq%20%27a%26b%27&$orderby=author/name+desc,title&$top=20&$skip=40&other=1",
+        )
+        .unwrap();
+        assert_eq!(query.filter.as_deref(), Some("name eq 'a&b'"));
+        assert_eq!(query.sort().as_deref(), Some("author.name:desc,title"));
+        assert_eq!(query.pagination().unwrap(), (Some(3), Some(20)));
+        assert!(ODataQuery::from_query("$top=-1").is_err());
+        assert!(ODataQuery::from_query("$top=20&$skip=10")
+            .unwrap()
+            .pagination()
+            .is_err());
+        assert!(ODataQuery::from_query("$skip=10")
+            .unwrap()
+            .pagination()
+            .is_err());
+    }
+
+    #[test]
+    fn comparisons_convert_their_values() {
+        assert_eq!(
+            comparison("id", Operator::Eq, &["1"]).value::<i32>(Operator::Eq),
+            Ok(1)
+        );
+        assert_eq!(
+            comparison("id", Operator::In, &["1", "2"]).values::<i32>(Operator::Eq),
+            Ok(vec![1, 2])
+        );
+        assert!(matches!(
+            comparison("id", Operator::Ne, &["1"]).value::<i32>(Operator::Eq),
+            Err(ODataError::UnsupportedOperator { .. })
+        ));
+        assert!(matches!(
+            comparison("id", Operator::Eq, &["x"]).value::<i32>(Operator::Eq),
+            Err(ODataError::InvalidValue { .. })
+        ));
+    }
+}
@@ -0,0 +1,22 @@
+//! The errors of the enums generated for the `#[filter(one_of(...))]` filters.
+use std::fmt;
+
+/// A value other than the ones accepted by a `one_of` filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OneOfError {
+    pub value: String,
+    pub expected: &'static [&'static str],
+}
+
+impl fmt::Display for OneOfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid value `{}`, expected one of: {}",
+            self.value,
+            self.expected.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for OneOfError {}
@@ -0,0 +1,50 @@
+//! Custom comparisons for the `#[filter(op = "...")]` filters, for the predicates
+//! the filter kinds don't cover.
+
+/// Registers a comparison under a name the `op` option of the filters refers to.
+/// The closure is given the column and a reference to the value of the filter,
+/// the values of a `multiple` filter, and returns a diesel boolean expression.
+///
+/// ```text
+/// diesel::define_sql_function!(fn lower(x: Text) -> Text);
+/// diesel::define_sql_function!(fn replace(x: Text, from: Text, to: Text) -> Text);
+///
+/// register_filter_op!(path_ci, |column, value| {
+///     lower(replace(column, "\\", "/")).eq(value.replace('\\', "/").to_lowercase())
+/// });
+///
+/// #[derive(Queryable, DieselFilter)]
+/// #[diesel(table_name = files)]
+/// pub struct File {
+///     pub id: Uuid,
+///     #[filter(op = "path_ci")]
+///     pub path: String,
+/// }
+/// ```
+///
+/// The name is a `macro_rules!` macro: registered before the models using it, or
+/// exported and referred to by its path, e.g `op = "crate::path_ci"`.
+#[macro_export]
+macro_rules! register_filter_op {
+    ($name:ident, |$column:pat, $value:pat| $body:expr) => {
+        $crate::__filter_op! { ($) $name, |$column, $value| $body }
+    };
+}
+
+// `$` can't be written in the expansion of a `macro_rules!`, it is passed along.
+// rustfmt shifts the nested macro further on every run
+#[doc(hidden)]
+#[macro_export]
+#[rustfmt::skip]
+macro_rules! __filter_op {
+    (($d:tt) $name:ident, |$column:pat, $value:pat| $body:expr) => {
+        #[allow(unused_macros)]
+        macro_rules! $name {
+            ($d column:expr, $d value:expr) => {
+                match ($d column, $d value) {
+                    ($column, $value) => $body,
+                }
+            };
+        }
+    };
+}
@@ -46,7 +46,7 @@ impl<T> Paginate for T {
         Paginated {
             query: self,
             per_page: DEFAULT_PER_PAGE,
-            page: page,
+            page,
             offset: (page - 1) * DEFAULT_PER_PAGE,
         }
     }
@@ -67,7 +67,7 @@ impl<T> Paginated<T> {
         Paginated {
             per_page,
             offset: (self.page - 1) * per_page,
-            ..self,
+            ..self
         }
     }
 
@@ -76,9 +76,9 @@ impl<T> Paginated<T> {
         Self: LoadQuery<'a, PgConnection, (U, i64)>,
     {
         let results = self.load::<(U, i64)>(conn)?;
-        let total = results.get(0).map(|x| x.1).unwrap_or(0);
+        let total = results.first().map(|x| x.1).unwrap_or(0);
         let records = results.into_iter().map(|x| x.0).collect();
-        let total_pages = total as i64;
+        let total_pages = total;
         Ok((records, total_pages))
     }
 }
@@ -104,7 +104,250 @@ where
     }
 }
 
+/// The number of rows matching the filters and of pages they fill, for the
+/// requests that don't need the rows themselves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PageInfo {
+    pub total: i64,
+    pub total_pages: i64,
+    /// The requested page, `1` by default
+    pub page: i64,
+}
+
+impl PageInfo {
+    /// `per_page` defaults to `DEFAULT_PER_PAGE` like `Paginated::per_page`
+    pub fn new(total: i64, per_page: Option<i64>) -> Self {
+        let per_page = per_page.unwrap_or(DEFAULT_PER_PAGE);
+        let total_pages = match per_page {
+            per_page if per_page > 0 => (total + per_page - 1) / per_page,
+            _ => 0,
+        };
+        PageInfo {
+            total,
+            total_pages,
+            page: 1,
+        }
+    }
+
+    pub fn with_page(self, page: Option<i64>) -> Self {
+        PageInfo {
+            page: page.unwrap_or(1),
+            ..self
+        }
+    }
+
+    /// The links of a pagination widget: the first and last pages, and the `n`
+    /// pages on each side of the current one, with gaps between them.
+    ///
+    /// ```text
+    /// page 5 of 20, n = 1: 1 … 4 5 6 … 20
+    /// page 2 of 20, n = 1: 1 2 3 … 20
+    /// ```
+    ///
+    /// A gap of a single page is that page rather than `…`.
+    pub fn page_window(&self, n: i64) -> Vec<PageLink> {
+        let mut pages: Vec<i64> = vec![1, self.total_pages];
+        let page = self.page.clamp(1, self.total_pages.max(1));
+        pages.extend((page - n.max(0))..=(page + n.max(0)));
+        pages.retain(|page| (1..=self.total_pages).contains(page));
+        pages.sort_unstable();
+        pages.dedup();
+
+        let mut links = vec![];
+        let mut last = 0;
+        for page in pages {
+            match page - last {
+                1 => {}
+                2 => links.push(PageLink::Page(last + 1)),
+                _ => links.push(PageLink::Gap),
+            }
+            links.push(PageLink::Page(page));
+            last = page;
+        }
+        links
+    }
+
+    /// The value of an RFC 5988 `Link` header linking to the `first`, `prev`, `next`
+    /// and `last` pages, empty when no row matches. The links are `base_url`, e.g the
+    /// URL of the request with its filters, with the `page` and `per_page` set.
+    ///
+    /// ```text
+    /// </projects?status=open&page=1&per_page=20>; rel="first", </projects?status=open&page=2&per_page=20>; rel="prev", ...
+    /// ```
+    pub fn link_header(&self, base_url: &str, per_page: Option<i64>) -> String {
+        let per_page = per_page.unwrap_or(DEFAULT_PER_PAGE);
+        let base_url = base_url.split('#').next().unwrap_or_default();
+        let (path, query) = base_url.split_once('?').unwrap_or((base_url, ""));
+        let mut params = query
+            .split('&')
+            .filter(|param| {
+                let key = param.split('=').next().unwrap_or_default();
+                !param.is_empty() && key != "page" && key != "per_page"
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        if !params.is_empty() {
+            params.push('&');
+        }
+
+        // The previous page of a page past the last one is the last one
+        let page = self.page.max(0);
+        let mut links = vec![];
+        if self.total_pages > 0 {
+            links.push((1, "first"));
+            if page > 1 {
+                links.push(((page - 1).min(self.total_pages), "prev"));
+            }
+            if page < self.total_pages {
+                links.push((page + 1, "next"));
+            }
+            links.push((self.total_pages, "last"));
+        }
+        links
+            .into_iter()
+            .map(|(page, rel)| {
+                format!(
+                    "<{}?{}page={}&per_page={}>; rel=\"{}\"",
+                    path, params, page, per_page, rel
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// A link of `PageInfo::page_window`, serialized as the number of the page or
+/// `null` for a gap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(untagged))]
+pub enum PageLink {
+    Page(i64),
+    Gap,
+}
+
+impl std::fmt::Display for PageLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PageLink::Page(page) => page.fmt(f),
+            PageLink::Gap => f.write_str("…"),
+        }
+    }
+}
+
 pub struct PaginationOptions {
     pub per_page: i64,
     pub page: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PageLink::{Gap, Page};
+
+    fn page_info(total_pages: i64, page: i64) -> PageInfo {
+        PageInfo::new(total_pages * 10, Some(10)).with_page(Some(page))
+    }
+
+    #[test]
+    fn page_window_gaps_the_distant_pages() {
+        assert_eq!(
+            page_info(20, 5).page_window(1),
+            vec![Page(1), Gap, Page(4), Page(5), Page(6), Gap, Page(20)]
+        );
+        assert_eq!(
+            page_info(20, 2).page_window(1),
+            vec![Page(1), Page(2), Page(3), Gap, Page(20)]
+        );
+        assert_eq!(
+            page_info(20, 5)
+                .page_window(1)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+            "1 … 4 5 6 … 20"
+        );
+    }
+
+    #[test]
+    fn page_window_fills_single_page_gaps() {
+        assert_eq!(
+            page_info(20, 4).page_window(1),
+            vec![Page(1), Page(2), Page(3), Page(4), Page(5), Gap, Page(20)]
+        );
+        assert_eq!(
+            page_info(5, 3).page_window(0),
+            vec![Page(1), Page(2), Page(3), Page(4), Page(5)]
+        );
+    }
+
+    #[test]
+    fn page_window_clamps_out_of_range_pages() {
+        assert_eq!(
+            page_info(20, 50).page_window(1),
+            vec![Page(1), Gap, Page(19), Page(20)]
+        );
+        assert_eq!(
+            page_info(20, -3).page_window(-1),
+            vec![Page(1), Gap, Page(20)]
+        );
+        assert_eq!(page_info(1, 1).page_window(2), vec![Page(1)]);
+    }
+
+    #[test]
+    fn page_window_of_no_rows_is_empty() {
+        assert_eq!(PageInfo::new(0, None).page_window(2), vec![]);
+        assert_eq!(PageInfo::new(10, Some(0)).page_window(2), vec![]);
+    }
+
+    #[test]
+    fn link_header_links_the_neighbouring_pages() {
+        assert_eq!(
+            page_info(5, 2).link_header("/projects?status=open", Some(10)),
+            "</projects?status=open&page=1&per_page=10>; rel=\"first\", \
+             </projects?status=open&page=1&per_page=10>; rel=\"prev\", \
+             </projects?status=open&page=3&per_page=10>; rel=\"next\", \
+             </projects?status=open&page=5&per_page=10>; rel=\"last\""
+        );
+        assert_eq!(
+            page_info(5, 1).link_header("/projects", None),
+            "</projects?page=1&per_page=10>; rel=\"first\", \
+             </projects?page=2&per_page=10>; rel=\"next\", \
+             </projects?page=5&per_page=10>; rel=\"last\""
+        );
+        assert_eq!(
+            page_info(5, 5).link_header("/projects", Some(3)),
+            "</projects?page=1&per_page=3>; rel=\"first\", \
+             </projects?page=4&per_page=3>; rel=\"prev\", \
+             </projects?page=5&per_page=3>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn link_header_replaces_the_paging_of_the_url() {
+        assert_eq!(
+            page_info(1, 1).link_header(
+                "/projects?page=3&status=open&&per_page=5&name=a%26b#top",
+                Some(20)
+            ),
+            "</projects?status=open&name=a%26b&page=1&per_page=20>; rel=\"first\", \
+             </projects?status=open&name=a%26b&page=1&per_page=20>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn link_header_past_the_last_page() {
+        assert_eq!(
+            page_info(5, 9).link_header("/projects", None),
+            "</projects?page=1&per_page=10>; rel=\"first\", \
+             </projects?page=5&per_page=10>; rel=\"prev\", \
+             </projects?page=5&per_page=10>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn link_header_of_no_rows_is_empty() {
+        assert_eq!(PageInfo::new(0, None).link_header("/projects", None), "");
+    }
+}
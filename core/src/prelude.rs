@@ -0,0 +1,21 @@
+//! The derive and the items the code calling the generated methods uses, in a
+//! single import. The generated code imports the traits it needs itself.
+//!
+//! ```text
+//! use diesel_filter::prelude::*;
+//! ```
+pub use crate::register_filter_op;
+pub use crate::{diesel_filter, diesel_filter_tests, DefaultFilters, DieselFilter, SortDirection};
+
+#[cfg(feature = "cache")]
+pub use crate::FilterCache;
+#[cfg(feature = "expr")]
+pub use crate::FilterExpr;
+#[cfg(feature = "tonic")]
+pub use crate::IntoFilter;
+#[cfg(feature = "replica")]
+pub use crate::ReadConnection;
+#[cfg(feature = "metrics")]
+pub use crate::{FilterEvent, FilterMetrics};
+#[cfg(feature = "pagination")]
+pub use crate::{PageInfo, PageLink, Paginate};
@@ -0,0 +1,46 @@
+/// Converts a field of a protobuf request into the value of a filter.
+///
+/// proto3 scalars can't be absent, so their default value (`""`, `0`, an
+/// empty `repeated` field) means that the filter is not set. Use `optional`
+/// fields when the default value is a legit filter.
+pub trait IntoFilter<T> {
+    fn into_filter(self) -> Option<T>;
+}
+
+impl<T> IntoFilter<T> for Option<T> {
+    fn into_filter(self) -> Option<T> {
+        self
+    }
+}
+
+impl IntoFilter<String> for String {
+    fn into_filter(self) -> Option<String> {
+        Some(self).filter(|v| !v.is_empty())
+    }
+}
+
+impl<T> IntoFilter<Vec<T>> for Vec<T> {
+    fn into_filter(self) -> Option<Vec<T>> {
+        Some(self).filter(|v| !v.is_empty())
+    }
+}
+
+macro_rules! impl_into_filter {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoFilter<$ty> for $ty {
+                fn into_filter(self) -> Option<$ty> {
+                    Some(self).filter(|v| *v != 0)
+                }
+            }
+        )*
+    };
+}
+
+impl_into_filter!(i32, i64, u32, u64);
+
+impl IntoFilter<i64> for i32 {
+    fn into_filter(self) -> Option<i64> {
+        Some(self.into()).filter(|v| *v != 0)
+    }
+}
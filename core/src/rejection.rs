@@ -0,0 +1,57 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+
+/// Rejection returned by the generated axum extractor when the query string
+/// cannot be deserialized into the filters struct.
+#[derive(Debug, Serialize)]
+pub struct FilterRejection {
+    pub parameter: Option<String>,
+    pub message: String,
+}
+
+impl FilterRejection {
+    pub fn from_query<T: DeserializeOwned>(query: Option<&str>) -> Result<T, Self> {
+        // `serde_html_form` collects repeated keys (`?id=1&id=2`) into the
+        // `Option<Vec<_>>` fields of `multiple` filters
+        let query = query.unwrap_or_default();
+        let deserializer = serde_html_form::Deserializer::from_bytes(query.as_bytes());
+
+        serde_path_to_error::deserialize(deserializer).map_err(|err| {
+            let parameter = match err.path().to_string() {
+                path if path == "." => None,
+                path => Some(path),
+            };
+
+            Self {
+                parameter,
+                message: err.into_inner().to_string(),
+            }
+        })
+    }
+}
+
+impl fmt::Display for FilterRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.parameter {
+            Some(parameter) => write!(
+                f,
+                "invalid query parameter `{}`: {}",
+                parameter, self.message
+            ),
+            None => write!(f, "invalid query string: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for FilterRejection {}
+
+impl IntoResponse for FilterRejection {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, Json(self)).into_response()
+    }
+}
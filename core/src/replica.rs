@@ -0,0 +1,25 @@
+//! Routing the read-only queries of the filters, e.g to a read replica while the
+//! rest of the application writes to the primary.
+use diesel::PgConnection;
+
+/// Chooses the connection `filtered_on` and `count_filtered_on` run on.
+///
+/// ```text
+/// struct Connections { primary: PgConnection, replica: PgConnection }
+///
+/// impl ReadConnection for Connections {
+///     fn read_conn(&mut self) -> &mut PgConnection {
+///         &mut self.replica
+///     }
+/// }
+/// ```
+pub trait ReadConnection {
+    fn read_conn(&mut self) -> &mut PgConnection;
+}
+
+/// A single connection serves the reads as well
+impl ReadConnection for PgConnection {
+    fn read_conn(&mut self) -> &mut PgConnection {
+        self
+    }
+}
@@ -0,0 +1,400 @@
+//! A parser for the subset of [RSQL](https://github.com/jirutka/rsql-parser)
+//! that maps onto the generated filters: a conjunction (`;` or `and`) of `==`
+//! comparisons, plus `=in=` for `multiple` filters and `>=`/`<=` for `gte`/`lte`
+//! filters.
+//!
+//! ```text
+//! name==foo*;category=in=(books,'board games')
+//! ```
+//!
+//! `*` wildcards are only meaningful for `substring` filters, which match
+//! anywhere in the column whatever the position of the wildcard.
+use std::{fmt, str::FromStr};
+
+/// A single `selector operator arguments` comparison.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub selector: String,
+    pub operator: String,
+    pub arguments: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RsqlError {
+    /// The expression is not valid RSQL, `position` is a byte offset
+    Syntax {
+        position: usize,
+        message: &'static str,
+    },
+    /// RSQL that is valid but has no equivalent in the filters (`,`, `or`,
+    /// nested groups)
+    Unsupported {
+        position: usize,
+        message: &'static str,
+    },
+    UnknownSelector(String),
+    UnsupportedOperator {
+        selector: String,
+        operator: String,
+    },
+    InvalidValue {
+        selector: String,
+        value: String,
+    },
+}
+
+impl fmt::Display for RsqlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RsqlError::Syntax { position, message } => {
+                write!(f, "invalid RSQL at {}: {}", position, message)
+            }
+            RsqlError::Unsupported { position, message } => {
+                write!(f, "unsupported RSQL at {}: {}", position, message)
+            }
+            RsqlError::UnknownSelector(selector) => write!(f, "unknown selector `{}`", selector),
+            RsqlError::UnsupportedOperator { selector, operator } => write!(
+                f,
+                "operator `{}` is not supported for `{}`",
+                operator, selector
+            ),
+            RsqlError::InvalidValue { selector, value } => {
+                write!(f, "invalid value `{}` for `{}`", value, selector)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RsqlError {}
+
+impl Constraint {
+    /// The argument of an `==` comparison.
+    pub fn value<T: FromStr>(&self) -> Result<T, RsqlError> {
+        match (self.operator.as_str(), self.arguments.as_slice()) {
+            ("==", [value]) => self.parse(value),
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    /// The arguments of an `==` or `=in=` comparison.
+    pub fn values<T: FromStr>(&self) -> Result<Vec<T>, RsqlError> {
+        match (self.operator.as_str(), self.arguments.as_slice()) {
+            ("==", [value]) => Ok(vec![self.parse(value)?]),
+            ("=in=", values) => values.iter().map(|value| self.parse(value)).collect(),
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    /// The argument of a comparison made with `operator`, e.g `>=`.
+    pub fn compared<T: FromStr>(&self, operator: &str) -> Result<T, RsqlError> {
+        match self.arguments.as_slice() {
+            [value] if self.operator == operator => self.parse(value),
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    /// Strips the `*` wildcards of the arguments, used by `substring` filters.
+    pub fn trim_wildcards(mut self) -> Self {
+        for argument in self.arguments.iter_mut() {
+            *argument = argument.trim_matches('*').to_owned();
+        }
+        self
+    }
+
+    fn parse<T: FromStr>(&self, value: &str) -> Result<T, RsqlError> {
+        value.parse().map_err(|_| RsqlError::InvalidValue {
+            selector: self.selector.clone(),
+            value: value.to_owned(),
+        })
+    }
+
+    fn unsupported(&self) -> RsqlError {
+        RsqlError::UnsupportedOperator {
+            selector: self.selector.clone(),
+            operator: self.operator.clone(),
+        }
+    }
+}
+
+/// Parses an RSQL expression into the list of its `and`-ed constraints.
+pub fn parse(input: &str) -> Result<Vec<Constraint>, RsqlError> {
+    let mut parser = Parser { input, position: 0 };
+    let mut constraints = vec![];
+
+    parser.skip_whitespace();
+    if parser.is_done() {
+        return Ok(constraints);
+    }
+    loop {
+        constraints.push(parser.constraint()?);
+        parser.skip_whitespace();
+        if parser.is_done() {
+            return Ok(constraints);
+        }
+        if !parser.eat(";") && !parser.eat_keyword("and") {
+            if parser.rest().starts_with(',') || parser.rest().starts_with("or ") {
+                return Err(parser.unsupported("`or` is not supported"));
+            }
+            return Err(parser.syntax("expected `;` or `and`"));
+        }
+        parser.skip_whitespace();
+    }
+}
+
+const RESERVED: &[char] = &['"', '\'', '(', ')', ';', ',', '=', '!', '~', '<', '>'];
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn is_done(&self) -> bool {
+        self.rest().is_empty()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let rest = self.rest();
+        let followed_by_space =
+            rest[keyword.len().min(rest.len())..].starts_with(char::is_whitespace);
+        if rest.starts_with(keyword) && followed_by_space {
+            self.position += keyword.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn syntax(&self, message: &'static str) -> RsqlError {
+        RsqlError::Syntax {
+            position: self.position,
+            message,
+        }
+    }
+
+    fn unsupported(&self, message: &'static str) -> RsqlError {
+        RsqlError::Unsupported {
+            position: self.position,
+            message,
+        }
+    }
+
+    fn constraint(&mut self) -> Result<Constraint, RsqlError> {
+        if self.peek() == Some('(') {
+            return Err(self.unsupported("groups are not supported"));
+        }
+        let selector = self.unreserved();
+        if selector.is_empty() {
+            return Err(self.syntax("expected a selector"));
+        }
+        self.skip_whitespace();
+        let operator = self.operator()?;
+        self.skip_whitespace();
+        let arguments = self.arguments()?;
+
+        Ok(Constraint {
+            selector,
+            operator,
+            arguments,
+        })
+    }
+
+    fn unreserved(&mut self) -> String {
+        let rest = self.rest();
+        let end = rest
+            .find(|c: char| c.is_whitespace() || RESERVED.contains(&c))
+            .unwrap_or(rest.len());
+        self.position += end;
+        rest[..end].to_owned()
+    }
+
+    fn operator(&mut self) -> Result<String, RsqlError> {
+        for operator in &["==", "!=", "<=", ">=", "<", ">"] {
+            if self.eat(operator) {
+                return Ok((*operator).to_owned());
+            }
+        }
+        // `=in=`, `=out=`, `=lt=`...
+        let rest = self.rest();
+        if let Some(named) = rest.strip_prefix('=') {
+            let len = named
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(named.len());
+            if len > 0 && named[len..].starts_with('=') {
+                self.position += len + 2;
+                return Ok(rest[..len + 2].to_owned());
+            }
+        }
+        Err(self.syntax("expected a comparison operator"))
+    }
+
+    fn arguments(&mut self) -> Result<Vec<String>, RsqlError> {
+        if !self.eat("(") {
+            return Ok(vec![self.value()?]);
+        }
+        let mut values = vec![];
+        loop {
+            self.skip_whitespace();
+            values.push(self.value()?);
+            self.skip_whitespace();
+            if self.eat(")") {
+                return Ok(values);
+            }
+            if !self.eat(",") {
+                return Err(self.syntax("expected `,` or `)`"));
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<String, RsqlError> {
+        match self.peek() {
+            Some(quote @ '"') | Some(quote @ '\'') => {
+                self.position += 1;
+                let mut value = String::new();
+                let mut chars = self.rest().char_indices();
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        '\\' => match chars.next() {
+                            Some((_, escaped)) => value.push(escaped),
+                            None => break,
+                        },
+                        c if c == quote => {
+                            self.position += i + 1;
+                            return Ok(value);
+                        }
+                        c => value.push(c),
+                    }
+                }
+                self.position = self.input.len();
+                Err(self.syntax("unterminated quoted value"))
+            }
+            _ => match self.unreserved() {
+                value if value.is_empty() => Err(self.syntax("expected a value")),
+                value => Ok(value),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constraint(selector: &str, operator: &str, arguments: &[&str]) -> Constraint {
+        Constraint {
+            selector: selector.to_owned(),
+            operator: operator.to_owned(),
+            arguments: arguments.iter().map(|a| (*a).to_owned()).collect(),
+        }
+    }
+
+    #[test]
+    fn parses_a_conjunction_of_comparisons() {
+        assert_eq!(
+            parse("name==foo*;category=in=(books, 'board games') and year>=2000").unwrap(),
+            vec![
+                constraint("name", "==", &["foo*"]),
+                constraint("category", "=in=", &["books", "board games"]),
+                constraint("year", ">=", &["2000"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn unescapes_quoted_values() {
+        assert_eq!(
+            parse(r#"title=="a \"quoted\" ;title";author=='O\'Brien'"#).unwrap(),
+            vec![
+                constraint("title", "==", &[r#"a "quoted" ;title"#]),
+                constraint("author", "==", &["O'Brien"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_input_has_no_constraints() {
+        assert_eq!(parse("").unwrap(), vec![]);
+        assert_eq!(parse("   ").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(matches!(
+            parse("==foo"),
+            Err(RsqlError::Syntax { position: 0, .. })
+        ));
+        assert!(matches!(parse("name"), Err(RsqlError::Syntax { .. })));
+        assert!(matches!(parse("name=="), Err(RsqlError::Syntax { .. })));
+        assert!(matches!(parse("name=='foo"), Err(RsqlError::Syntax { .. })));
+        assert!(matches!(parse("id=in=(1,2"), Err(RsqlError::Syntax { .. })));
+        assert!(matches!(parse("a==1 b==2"), Err(RsqlError::Syntax { .. })));
+    }
+
+    #[test]
+    fn rejects_disjunctions_and_groups() {
+        assert!(matches!(
+            parse("a==1,b==2"),
+            Err(RsqlError::Unsupported { .. })
+        ));
+        assert!(matches!(
+            parse("a==1 or b==2"),
+            Err(RsqlError::Unsupported { .. })
+        ));
+        assert!(matches!(
+            parse("(a==1;b==2)"),
+            Err(RsqlError::Unsupported { .. })
+        ));
+    }
+
+    #[test]
+    fn constraints_convert_their_arguments() {
+        assert_eq!(constraint("id", "==", &["1"]).value::<i32>(), Ok(1));
+        assert_eq!(
+            constraint("id", "=in=", &["1", "2"]).values::<i32>(),
+            Ok(vec![1, 2])
+        );
+        assert_eq!(
+            constraint("year", ">=", &["2000"]).compared::<i32>(">="),
+            Ok(2000)
+        );
+        assert_eq!(
+            constraint("id", "==", &["x"]).value::<i32>(),
+            Err(RsqlError::InvalidValue {
+                selector: "id".to_owned(),
+                value: "x".to_owned(),
+            })
+        );
+        assert!(matches!(
+            constraint("id", "!=", &["1"]).value::<i32>(),
+            Err(RsqlError::UnsupportedOperator { .. })
+        ));
+        assert_eq!(
+            constraint("name", "==", &["*foo*"])
+                .trim_wildcards()
+                .arguments,
+            vec!["foo".to_owned()]
+        );
+    }
+}
@@ -0,0 +1,16 @@
+//! Filters saved as JSON, e.g in a JSONB column, and replayed later.
+//!
+//! The filters are serialized under their field names, unset filters are left
+//! out so saved searches stay valid when filters are added to the model.
+use serde::{de::DeserializeOwned, Serialize};
+pub use serde_json::Error;
+
+/// Reads filters saved with [`to_json_string`].
+pub fn from_json_str<T: DeserializeOwned>(json: &str) -> Result<T, Error> {
+    serde_json::from_str(json)
+}
+
+/// Serializes filters to a JSON object.
+pub fn to_json_string<T: Serialize>(filters: &T) -> Result<String, Error> {
+    serde_json::to_string(filters)
+}
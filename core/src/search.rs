@@ -0,0 +1,195 @@
+//! A GitHub style search syntax: `status:open author:"jane doe" urgent`.
+//!
+//! `key:value` qualifiers set the filter named `key`, repeating a qualifier
+//! or separating values with commas (`label:bug,docs`) adds values to
+//! `multiple` filters. The remaining words make up the `search` filter of the
+//! structs declaring `#[diesel_filter(search(...))]` columns.
+use std::{fmt, str::FromStr};
+
+/// The qualifiers and the free text words of a search.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchTerms {
+    pub qualifiers: Vec<(String, String)>,
+    pub words: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchSyntaxError {
+    /// `position` is the byte offset of the opening quote
+    UnterminatedQuote {
+        position: usize,
+    },
+    UnknownQualifier(String),
+    InvalidValue {
+        qualifier: String,
+        value: String,
+    },
+    /// Free text on filters without a `search` field
+    UnexpectedText(String),
+}
+
+impl fmt::Display for SearchSyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchSyntaxError::UnterminatedQuote { position } => {
+                write!(f, "unterminated quote at {}", position)
+            }
+            SearchSyntaxError::UnknownQualifier(qualifier) => {
+                write!(f, "unknown qualifier `{}`", qualifier)
+            }
+            SearchSyntaxError::InvalidValue { qualifier, value } => {
+                write!(f, "invalid value `{}` for `{}`", value, qualifier)
+            }
+            SearchSyntaxError::UnexpectedText(text) => {
+                write!(
+                    f,
+                    "unexpected text `{}`, only qualifiers are supported",
+                    text
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SearchSyntaxError {}
+
+/// Splits a search into its `key:value` qualifiers and free text words,
+/// double quotes group words with spaces.
+pub fn parse(input: &str) -> Result<SearchTerms, SearchSyntaxError> {
+    let mut terms = SearchTerms::default();
+    let mut chars = input.char_indices().peekable();
+
+    loop {
+        while chars.peek().is_some_and(|(_, c)| c.is_whitespace()) {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            return Ok(terms);
+        }
+
+        let mut key = None;
+        let mut text = String::new();
+        while let Some((position, c)) = chars.next() {
+            match c {
+                c if c.is_whitespace() => break,
+                ':' if key.is_none() && !text.is_empty() => key = Some(std::mem::take(&mut text)),
+                '"' => loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, c)) => text.push(c),
+                        None => return Err(SearchSyntaxError::UnterminatedQuote { position }),
+                    }
+                },
+                c => text.push(c),
+            }
+        }
+
+        match key {
+            Some(key) => terms.qualifiers.push((key, text)),
+            None if text.is_empty() => {}
+            None => terms.words.push(text),
+        }
+    }
+}
+
+/// The value of a qualifier.
+pub fn value<T: FromStr>(qualifier: &str, value: &str) -> Result<T, SearchSyntaxError> {
+    match value {
+        "" => Err(invalid(qualifier, value)),
+        value => value.parse().map_err(|_| invalid(qualifier, value)),
+    }
+}
+
+/// The comma separated values of a qualifier.
+pub fn values<T: FromStr>(qualifier: &str, values: &str) -> Result<Vec<T>, SearchSyntaxError> {
+    let parsed = values
+        .split(',')
+        .filter(|v| !v.is_empty())
+        .map(|v| value(qualifier, v))
+        .collect::<Result<Vec<_>, _>>()?;
+    match parsed.is_empty() {
+        true => Err(invalid(qualifier, values)),
+        false => Ok(parsed),
+    }
+}
+
+fn invalid(qualifier: &str, value: &str) -> SearchSyntaxError {
+    SearchSyntaxError::InvalidValue {
+        qualifier: qualifier.to_owned(),
+        value: value.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn qualifiers(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn splits_qualifiers_and_words() {
+        let terms = parse("status:open urgent  label:bug,docs fix").unwrap();
+        assert_eq!(
+            terms.qualifiers,
+            qualifiers(&[("status", "open"), ("label", "bug,docs")])
+        );
+        assert_eq!(terms.words, vec!["urgent", "fix"]);
+    }
+
+    #[test]
+    fn quotes_group_words() {
+        let terms = parse(r#"author:"jane doe" "a:b c" x"y z""#).unwrap();
+        assert_eq!(terms.qualifiers, qualifiers(&[("author", "jane doe")]));
+        assert_eq!(terms.words, vec!["a:b c", "xy z"]);
+        assert_eq!(
+            parse(r#"name:"""#).unwrap().qualifiers,
+            qualifiers(&[("name", "")])
+        );
+    }
+
+    #[test]
+    fn empty_search_has_no_terms() {
+        assert_eq!(parse("").unwrap(), SearchTerms::default());
+        assert_eq!(parse(" \t\n").unwrap(), SearchTerms::default());
+        assert_eq!(parse(r#""""#).unwrap(), SearchTerms::default());
+    }
+
+    #[test]
+    fn colons_after_the_key_are_in_the_value() {
+        let terms = parse(":open at:12:30").unwrap();
+        assert_eq!(terms.qualifiers, qualifiers(&[("at", "12:30")]));
+        assert_eq!(terms.words, vec![":open"]);
+    }
+
+    #[test]
+    fn rejects_unterminated_quotes() {
+        assert_eq!(
+            parse(r#"status:open author:"jane doe"#),
+            Err(SearchSyntaxError::UnterminatedQuote { position: 19 })
+        );
+    }
+
+    #[test]
+    fn parses_values() {
+        assert_eq!(value::<i32>("priority", "3"), Ok(3));
+        assert_eq!(values::<i32>("priority", "1,,2,"), Ok(vec![1, 2]));
+        assert_eq!(value::<i32>("priority", ""), Err(invalid("priority", "")));
+        assert_eq!(
+            value::<i32>("priority", "high"),
+            Err(invalid("priority", "high"))
+        );
+        assert_eq!(
+            values::<i32>("priority", ","),
+            Err(invalid("priority", ","))
+        );
+        assert_eq!(
+            values::<i32>("priority", "1,x"),
+            Err(invalid("priority", "x"))
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! The direction of a sort, parsed the same way by every model and framework:
+//! `asc` or `desc`, whatever the case, and the `sort` parameter of the models
+//! with `#[sortable]` fields.
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// A direction other than `asc` or `desc`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortDirectionError(pub String);
+
+impl fmt::Display for SortDirectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid sort direction `{}`, expected `asc` or `desc`",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for SortDirectionError {}
+
+/// Where the `NULL`s of a column are sorted, Postgres puts them last in ascending
+/// order and first in descending order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+/// A term of a `sort` parameter, `key:direction:nulls_first` or `nulls_last`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortTerm<'a> {
+    pub key: &'a str,
+    pub direction: SortDirection,
+    /// `None` for the order of the `#[sortable]` field
+    pub nulls: Option<NullsOrder>,
+}
+
+/// A `sort` parameter naming a key the model doesn't sort by, or with an invalid
+/// direction or order of the `NULL`s
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortError {
+    UnknownKey {
+        key: String,
+        expected: &'static [&'static str],
+    },
+    Direction(SortDirectionError),
+    Nulls(String),
+}
+
+impl fmt::Display for SortError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortError::UnknownKey { key, expected } => write!(
+                f,
+                "invalid sort key `{}`, expected one of: {}",
+                key,
+                expected.join(", ")
+            ),
+            SortError::Direction(err) => err.fmt(f),
+            SortError::Nulls(nulls) => write!(
+                f,
+                "invalid order of the nulls `{}`, expected `nulls_first` or `nulls_last`",
+                nulls
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SortError {}
+
+/// The terms of a `sort` parameter, e.g `name,created_at:desc:nulls_last`, the
+/// direction is `asc` when left out
+pub fn terms(sort: &str) -> impl Iterator<Item = Result<SortTerm<'_>, SortError>> + '_ {
+    sort.split(',')
+        .map(str::trim)
+        .filter(|term| !term.is_empty())
+        .map(|term| {
+            let mut parts = term.split(':').map(str::trim);
+            let key = parts.next().unwrap_or_default();
+            let direction = match parts.next() {
+                Some(direction) => direction.parse().map_err(SortError::Direction)?,
+                None => SortDirection::Asc,
+            };
+            let nulls = match parts.next() {
+                None => None,
+                Some(nulls) if nulls.eq_ignore_ascii_case("nulls_first") => Some(NullsOrder::First),
+                Some(nulls) if nulls.eq_ignore_ascii_case("nulls_last") => Some(NullsOrder::Last),
+                Some(nulls) => return Err(SortError::Nulls(nulls.to_owned())),
+            };
+            match parts.next() {
+                Some(extra) => Err(SortError::Nulls(extra.to_owned())),
+                None => Ok(SortTerm {
+                    key,
+                    direction,
+                    nulls,
+                }),
+            }
+        })
+}
+
+/// Checks that every term of `sort` is valid and has one of the `keys`
+pub fn check(sort: &str, keys: &'static [&'static str]) -> Result<(), SortError> {
+    for term in terms(sort) {
+        let key = term?.key;
+        if !keys.contains(&key) {
+            return Err(SortError::UnknownKey {
+                key: key.to_owned(),
+                expected: keys,
+            });
+        }
+    }
+    Ok(())
+}
+
+impl FromStr for SortDirection {
+    type Err = SortDirectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            s if s.eq_ignore_ascii_case("asc") => Ok(SortDirection::Asc),
+            s if s.eq_ignore_ascii_case("desc") => Ok(SortDirection::Desc),
+            s => Err(SortDirectionError(s.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for SortDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortDirection::Asc => f.write_str("asc"),
+            SortDirection::Desc => f.write_str("desc"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SortDirection {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SortDirection {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "rocket")]
+impl<'v> rocket::form::FromFormField<'v> for SortDirection {
+    fn from_value(field: rocket::form::ValueField<'v>) -> rocket::form::Result<'v, Self> {
+        field.value.parse().map_err(|err: SortDirectionError| {
+            rocket::form::Error::validation(err.to_string()).into()
+        })
+    }
+}
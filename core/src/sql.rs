@@ -0,0 +1,240 @@
+/// Redacts the string values bound in the output of `diesel::debug_query`,
+/// keeping their first `keep` characters.
+///
+/// ```text
+/// ... WHERE ("users"."email" = $1) -- binds: ["jane@example.com"]
+/// keep = 0: ... WHERE ("users"."email" = $1) -- binds: ["***"]
+/// keep = 4: ... WHERE ("users"."email" = $1) -- binds: ["jane…"]
+/// ```
+pub fn redact_binds(sql: &str, keep: usize) -> String {
+    let (query, binds) = match sql.rfind(" -- binds: ") {
+        Some(i) => sql.split_at(i),
+        None => return sql.to_owned(),
+    };
+
+    let mut redacted = String::with_capacity(sql.len());
+    redacted.push_str(query);
+    let mut chars = binds.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            redacted.push(c);
+            continue;
+        }
+
+        // the binds are `Debug` formatted, quotes inside strings are escaped
+        let mut value = String::new();
+        let mut len = 0;
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => {
+                    if len < keep {
+                        value.push(c);
+                        value.extend(chars.next());
+                    } else {
+                        chars.next();
+                    }
+                }
+                c if len < keep => value.push(c),
+                _ => {}
+            }
+            len += 1;
+        }
+        redacted.push('"');
+        match keep {
+            0 => redacted.push_str("***"),
+            keep if len > keep => {
+                redacted.push_str(&value);
+                redacted.push('…');
+            }
+            _ => redacted.push_str(&value),
+        }
+        redacted.push('"');
+    }
+    redacted
+}
+
+/// Splits the `search` filter into the text to find and the `-term` words to
+/// exclude. A search without exclusions is left as is.
+///
+/// ```text
+/// "rust -draft -old" -> ("rust", ["draft", "old"])
+/// ```
+pub fn split_search(search: &str) -> (String, Vec<String>) {
+    let mut text = vec![];
+    let mut excluded = vec![];
+    for word in search.split_whitespace() {
+        match word.strip_prefix('-') {
+            Some(term) if !term.is_empty() => excluded.push(term.to_owned()),
+            _ => text.push(word),
+        }
+    }
+    match excluded.is_empty() {
+        true => (search.to_owned(), excluded),
+        false => (text.join(" "), excluded),
+    }
+}
+
+/// The position of the `WHERE` keyword of `sql` outside of its parentheses,
+/// string literals, quoted identifiers and comments, if it has one.
+///
+/// ```text
+/// "SELECT * FROM t WHERE a = 1" -> Some(16)
+/// "SELECT * FROM t WHERE a IN (SELECT b FROM u WHERE c)" -> Some(16)
+/// "SELECT (SELECT b FROM u WHERE c) FROM t" -> None
+/// ```
+pub fn top_level_where(sql: &str) -> Option<usize> {
+    let bytes = sql.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            quote @ (b'\'' | b'"') => {
+                // a doubled quote is a quote inside the literal
+                i += 1;
+                while i < bytes.len() {
+                    if bytes[i] == quote {
+                        if bytes.get(i + 1) != Some(&quote) {
+                            break;
+                        }
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'(' => depth += 1,
+            b')' => depth = depth.saturating_sub(1),
+            c if depth == 0 && (c.is_ascii_alphabetic() || c == b'_') => {
+                let start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                if sql[start..i].eq_ignore_ascii_case("where") {
+                    return Some(start);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQL: &str = r#"SELECT "users"."id" FROM "users" WHERE ("users"."email" = $1)"#;
+
+    #[test]
+    fn redact_binds_hides_the_strings() {
+        let sql = format!(r#"{} -- binds: ["jane@example.com", 3]"#, SQL);
+        assert_eq!(
+            redact_binds(&sql, 0),
+            format!(r#"{} -- binds: ["***", 3]"#, SQL)
+        );
+        assert_eq!(
+            redact_binds(&sql, 4),
+            format!(r#"{} -- binds: ["jane…", 3]"#, SQL)
+        );
+        assert_eq!(redact_binds(&sql, 16), sql);
+    }
+
+    #[test]
+    fn redact_binds_keeps_escaped_quotes_in_the_strings() {
+        let sql = format!(r#"{} -- binds: ["a\"b", "\\"]"#, SQL);
+        assert_eq!(redact_binds(&sql, 3), sql);
+        assert_eq!(
+            redact_binds(&sql, 1),
+            format!(r#"{} -- binds: ["a…", "\\"]"#, SQL)
+        );
+        assert_eq!(
+            redact_binds(&sql, 0),
+            format!(r#"{} -- binds: ["***", "***"]"#, SQL)
+        );
+    }
+
+    #[test]
+    fn redact_binds_of_empty_binds() {
+        assert_eq!(redact_binds(SQL, 0), SQL);
+        assert_eq!(redact_binds("", 0), "");
+        let sql = format!(r#"{} -- binds: [""]"#, SQL);
+        assert_eq!(redact_binds(&sql, 2), sql);
+        assert_eq!(
+            redact_binds(&sql, 0),
+            format!(r#"{} -- binds: ["***"]"#, SQL)
+        );
+    }
+
+    #[test]
+    fn split_search_excludes_the_dashed_words() {
+        assert_eq!(
+            split_search("rust -draft  -old"),
+            (
+                "rust".to_owned(),
+                vec!["draft".to_owned(), "old".to_owned()]
+            )
+        );
+        assert_eq!(
+            split_search("-draft rust - re-use"),
+            ("rust - re-use".to_owned(), vec!["draft".to_owned()])
+        );
+        assert_eq!(
+            split_search("--draft"),
+            (String::new(), vec!["-draft".to_owned()])
+        );
+    }
+
+    #[test]
+    fn split_search_leaves_searches_without_exclusions() {
+        assert_eq!(
+            split_search("  rust  diesel "),
+            ("  rust  diesel ".to_owned(), vec![])
+        );
+        assert_eq!(split_search("- rust"), ("- rust".to_owned(), vec![]));
+        assert_eq!(split_search(""), (String::new(), vec![]));
+    }
+
+    #[test]
+    fn top_level_where_finds_the_clause_of_the_query() {
+        assert_eq!(top_level_where("SELECT * FROM t WHERE a = 1"), Some(16));
+        assert_eq!(top_level_where("SELECT * FROM t where a = 1"), Some(16));
+        assert_eq!(
+            top_level_where("SELECT * FROM t WHERE a IN (SELECT b FROM u WHERE c)"),
+            Some(16)
+        );
+    }
+
+    #[test]
+    fn top_level_where_skips_subqueries_quotes_and_comments() {
+        assert_eq!(
+            top_level_where("SELECT (SELECT b FROM u WHERE c) FROM t"),
+            None
+        );
+        assert_eq!(top_level_where("SELECT 'where' FROM t"), None);
+        assert_eq!(top_level_where("SELECT 'it''s where' FROM t"), None);
+        assert_eq!(top_level_where("SELECT \"where\" FROM t"), None);
+        assert_eq!(top_level_where("SELECT * FROM t -- where\n"), None);
+        assert_eq!(top_level_where("SELECT * FROM t /* where */"), None);
+        assert_eq!(top_level_where("SELECT somewhere, where_ FROM t"), None);
+    }
+
+    #[test]
+    fn top_level_where_of_empty_sql() {
+        assert_eq!(top_level_where(""), None);
+        assert_eq!(top_level_where("'unterminated where"), None);
+    }
+}
@@ -0,0 +1,61 @@
+//! Filtered queries over several tables sharing the columns of the table of the
+//! model, e.g per-month partitions of an archive.
+use diesel::{pg::Pg, prelude::*, query_builder::*};
+
+/// `query` run with its table shadowed by the rows of `tables`:
+///
+/// ```text
+/// WITH "events" AS (SELECT * FROM "events_2024_01" UNION ALL SELECT * FROM "events_2024_02")
+/// SELECT "events"."id", ... FROM "events" WHERE ...
+/// ```
+///
+/// The columns of the query are qualified with the name of the table, so every
+/// filter applies to the rows of the union unchanged.
+#[derive(Debug, Clone, Copy)]
+pub struct UnionAll<T> {
+    query: T,
+    table: &'static str,
+    tables: &'static [&'static str],
+}
+
+impl<T> UnionAll<T> {
+    pub fn new(query: T, table: &'static str, tables: &'static [&'static str]) -> Self {
+        UnionAll {
+            query,
+            table,
+            tables,
+        }
+    }
+}
+
+// The SQL depends on the tables, not only on the type of the query
+impl<T> QueryId for UnionAll<T> {
+    type QueryId = ();
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<T: Query> Query for UnionAll<T> {
+    type SqlType = T::SqlType;
+}
+
+impl<T> RunQueryDsl<PgConnection> for UnionAll<T> {}
+
+impl<T> QueryFragment<Pg> for UnionAll<T>
+where
+    T: QueryFragment<Pg>,
+{
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        out.push_sql("WITH ");
+        out.push_identifier(self.table)?;
+        out.push_sql(" AS (");
+        for (i, table) in self.tables.iter().enumerate() {
+            if i > 0 {
+                out.push_sql(" UNION ALL ");
+            }
+            out.push_sql("SELECT * FROM ");
+            out.push_identifier(table)?;
+        }
+        out.push_sql(") ");
+        self.query.walk_ast(out.reborrow())
+    }
+}
@@ -1,16 +1,137 @@
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span};
-use quote::{quote, ToTokens};
+use quote::{quote, quote_spanned, ToTokens};
 use std::default::Default;
 use syn::{
+    meta::ParseNestedMeta,
+    parenthesized,
     parse::{Parse, ParseStream},
-    parse_macro_input, Data, DeriveInput, Fields, Meta, NestedMeta, Path, Token, Type, TypePath,
+    parse_macro_input, parse_quote, token, Attribute, Data, DeriveInput, Expr, Fields,
+    GenericParam, LitStr, Meta, Path, Token, Type, TypePath,
 };
 
+/// Web frameworks the generated filters struct is wired into
+#[derive(Clone, Copy)]
+struct Frontends {
+    rocket: bool,
+    actix: bool,
+    axum: bool,
+    aide: bool,
+    poem: bool,
+    warp: bool,
+    salvo: bool,
+    juniper: bool,
+}
+
+impl Frontends {
+    /// Every framework enabled through the crate features
+    fn enabled() -> Self {
+        Self {
+            rocket: cfg!(feature = "rocket"),
+            actix: cfg!(feature = "actix"),
+            axum: cfg!(feature = "axum"),
+            aide: cfg!(feature = "aide"),
+            poem: cfg!(feature = "poem"),
+            warp: cfg!(feature = "warp"),
+            salvo: cfg!(feature = "salvo"),
+            juniper: cfg!(feature = "juniper"),
+        }
+    }
+
+    /// Only the framework selected with `#[diesel_filter(frontend = "...")]`,
+    /// its crate feature must be enabled as well
+    fn only(lit: &LitStr) -> syn::Result<Self> {
+        let frontend = lit.value();
+        let enabled = Self::enabled();
+        let none = Self {
+            rocket: false,
+            actix: false,
+            axum: false,
+            aide: false,
+            poem: false,
+            warp: false,
+            salvo: false,
+            juniper: false,
+        };
+        let (selected, available) = match frontend.as_str() {
+            "rocket" => (
+                Self {
+                    rocket: true,
+                    ..none
+                },
+                enabled.rocket,
+            ),
+            "actix" => (
+                Self {
+                    actix: true,
+                    ..none
+                },
+                enabled.actix,
+            ),
+            "axum" => (
+                Self {
+                    axum: true,
+                    aide: enabled.aide,
+                    ..none
+                },
+                enabled.axum,
+            ),
+            "poem" => (Self { poem: true, ..none }, enabled.poem),
+            "warp" => (Self { warp: true, ..none }, enabled.warp),
+            "salvo" => (
+                Self {
+                    salvo: true,
+                    ..none
+                },
+                enabled.salvo,
+            ),
+            "juniper" => (
+                Self {
+                    juniper: true,
+                    ..none
+                },
+                enabled.juniper,
+            ),
+            _ => {
+                return Err(syn::Error::new(
+                    lit.span(),
+                    "unknown frontend, expected one of `rocket`, `actix`, `axum`, `poem`, `warp`, `salvo` or `juniper`",
+                ))
+            }
+        };
+        if !available {
+            return Err(syn::Error::new(
+                lit.span(),
+                format!(
+                    "frontend `{}` requires the `{}` feature of diesel_filter",
+                    frontend, frontend
+                ),
+            ));
+        }
+        Ok(selected)
+    }
+
+    /// Whether the generated filters struct derives `serde::Deserialize` and `serde::Serialize`
+    fn serde(&self) -> bool {
+        self.actix || self.axum || self.poem || self.warp || self.salvo
+    }
+}
+
 struct Filter {
     pub name: Ident,
+    /// The field of the model, named `name` unless renamed
+    pub field: Ident,
+    pub column: Ident,
     pub ty: FilterableType,
     pub opts: FilterOpts,
+    pub garde: Vec<Attribute>,
+    pub docs: Vec<Attribute>,
+    /// Whether the field is an `Option`
+    pub nullable: bool,
+    /// Whether the column is a `timestamptz`, for the filters with a `tz` parameter
+    pub with_time_zone: bool,
+    /// Whether the column is an `interval`, filtered with strings cast by Postgres
+    pub interval: bool,
 }
 
 enum FilterableType {
@@ -24,304 +145,6179 @@ enum FilterKind {
     Substr,
     Insensitive,
     SubstrInsensitive,
+    /// `lower(col) = lower(value)`, without `ILIKE` or `citext`
+    Lower,
+    /// Array columns containing all the values
+    Contains,
+    /// Array columns containing any of the values
+    Overlaps,
+    /// `bytea` columns equal to the hex decoded value
+    Bytea,
+    /// `bytea` columns starting with the hex decoded value
+    Prefix,
+    /// Columns greater than or equal to the value
+    Gte,
+    /// Columns less than or equal to the value
+    Lte,
+    /// Text columns matching a web search style full-text query
+    Fulltext,
+    /// Integer bitmasks with all the bits of the value set, `(col & value) = value`
+    HasFlags,
+    /// `hstore` columns with the key, `col ? key`
+    HstoreKey,
+    /// `hstore` columns with the value at the key of a `key:value` pair, `(col -> key) = value`
+    HstoreKv,
+}
+
+impl FilterKind {
+    fn is_array(&self) -> bool {
+        matches!(self, FilterKind::Contains | FilterKind::Overlaps)
+    }
 }
 
 struct FilterOpts {
+    /// Former names of the parameter still accepted
+    aliases: Vec<String>,
+    /// Whether `filtered_facets` counts the rows per value of the column
+    facet: bool,
     multiple: bool,
+    /// Values a `multiple` filter takes at most
+    max_values: Option<usize>,
+    /// Whether the values over `max_values` are dropped rather than rejected
+    truncate_values: bool,
+    /// Values of a `multiple` filter `filtered_chunked` runs a query per
+    chunk_size: Option<usize>,
+    delimiter: Option<String>,
+    proto: Option<String>,
+    /// Note of the `#[deprecated]` the generated field is marked with
+    deprecated: Option<String>,
+    /// Name of the generated filter when it isn't the name of the field
+    rename: Option<Ident>,
+    validate: Option<proc_macro2::TokenStream>,
+    /// Parameter holding the time zone the dates of the column are compared in
+    tz: Option<Ident>,
+    /// Values accepted by the filter, parsed into an enum generated for it
+    one_of: Vec<LitStr>,
+    /// Table holding the `tsvector` of the column, searched instead of the column
+    fts_table: Option<String>,
+    /// Related rows the filter matches, rather than the column of the field
+    exists_in: Option<Subquery>,
+    /// Comparison registered with `register_filter_op!`, instead of the kind
+    op: Option<Path>,
+    /// Type of the values of the filter when it isn't the type of the field, e.g
+    /// the `String` of an `EmailAddress(String)` newtype, or the type a `ty` alias
+    /// stands for
+    as_ty: Option<Type>,
+    /// Predicate of the `#[cfg]` the field and its conditions are compiled under
+    cfg: Option<proc_macro2::TokenStream>,
     kind: FilterKind,
 }
 
+/// A correlated subquery on the rows of `table` whose `on` column references
+/// the column of the field, filtering their `column`. Without a column the
+/// filter is a boolean on whether there are related rows at all
+struct Subquery {
+    table: String,
+    on: String,
+    column: Option<String>,
+    related: Related,
+}
+
+enum Related {
+    /// `EXISTS`, the rows with matching related rows
+    Exists,
+    /// `NOT EXISTS`, the rows without matching related rows
+    NotExists,
+    /// The rows whose number of related rows compares to the value
+    Count,
+}
+
+/// Rejects the options that are not in `known`, typos would be silently ignored otherwise
+fn unknown_option(meta: &ParseNestedMeta, known: &[&str]) -> syn::Error {
+    meta.error(format!(
+        "unknown option `{}`, expected one of: {}",
+        meta.path.to_token_stream().to_string().replace(' ', ""),
+        known.join(", ")
+    ))
+}
+
 impl Default for FilterOpts {
     fn default() -> Self {
         Self {
+            aliases: vec![],
+            facet: false,
             multiple: false,
+            max_values: None,
+            truncate_values: false,
+            chunk_size: None,
+            delimiter: None,
+            proto: None,
+            deprecated: None,
+            rename: None,
+            validate: None,
+            tz: None,
+            one_of: vec![],
+            fts_table: None,
+            exists_in: None,
+            op: None,
+            as_ty: None,
+            cfg: None,
             kind: FilterKind::Basic,
         }
     }
 }
 
-impl From<Vec<NestedMeta>> for FilterOpts {
-    fn from(m: Vec<NestedMeta>) -> Self {
-        let meta = m
-            .into_iter()
-            .filter_map(|m| match m {
-                NestedMeta::Meta(m) => Some(m.path().to_owned()),
-                _ => None,
-            })
-            .collect::<Vec<_>>();
+impl FilterOpts {
+    /// The `#[cfg]` of everything generated for the filter, nothing without the option
+    fn cfg_attr(&self) -> proc_macro2::TokenStream {
+        match &self.cfg {
+            Some(predicate) => quote! { #[cfg(#predicate)] },
+            None => quote! {},
+        }
+    }
 
-        let matches =
-            |m: &Vec<Path>, tested: &[&str]| tested.iter().all(|t| m.iter().any(|m| m.is_ident(t)));
+    fn parse(attr: &Attribute) -> syn::Result<Self> {
+        let mut opts = Self::default();
+        if let Meta::Path(_) = attr.meta {
+            return Ok(opts);
+        }
 
-        let kind = if matches(&meta, &["substring", "insensitive"]) {
-            FilterKind::SubstrInsensitive
-        } else if matches(&meta, &["substring"]) {
-            FilterKind::Substr
-        } else if matches(&meta, &["insensitive"]) {
-            FilterKind::Insensitive
-        } else {
-            FilterKind::Basic
+        let (mut substring, mut insensitive, mut lower) = (false, false, false);
+        let (mut contains, mut overlaps, mut prefix) = (false, false, false);
+        let (mut gte, mut lte, mut fulltext) = (false, false, false);
+        let (mut exact, mut has_flags) = (false, false);
+        let (mut hstore_key, mut hstore_kv) = (false, false);
+        let mut overflow_set = false;
+        let (mut exists_in, mut not_exists_in, mut count_in) = (None, None, None);
+        let (mut on, mut column) = (None, None);
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("alias") {
+                opts.aliases.push(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("facet") {
+                opts.facet = true;
+            } else if meta.path.is_ident("multiple") {
+                opts.multiple = true;
+            } else if meta.path.is_ident("substring") {
+                substring = true;
+            } else if meta.path.is_ident("insensitive") {
+                insensitive = true;
+            } else if meta.path.is_ident("exact") {
+                exact = true;
+            } else if meta.path.is_ident("lower") {
+                lower = true;
+            } else if meta.path.is_ident("contains") {
+                contains = true;
+            } else if meta.path.is_ident("overlaps") {
+                overlaps = true;
+            } else if meta.path.is_ident("prefix") {
+                prefix = true;
+            } else if meta.path.is_ident("gte") {
+                gte = true;
+            } else if meta.path.is_ident("lte") {
+                lte = true;
+            } else if meta.path.is_ident("fulltext") {
+                fulltext = true;
+            } else if meta.path.is_ident("has_flags") {
+                has_flags = true;
+            } else if meta.path.is_ident("hstore_key") {
+                hstore_key = true;
+            } else if meta.path.is_ident("hstore_kv") {
+                hstore_kv = true;
+            } else if meta.path.is_ident("kind") {
+                let kind: LitStr = meta.value()?.parse()?;
+                match kind.value().as_str() {
+                    "basic" => {}
+                    "substring" => substring = true,
+                    "insensitive" => insensitive = true,
+                    "substring_insensitive" => {
+                        substring = true;
+                        insensitive = true;
+                    }
+                    "lower" => lower = true,
+                    "contains" => contains = true,
+                    "overlaps" => overlaps = true,
+                    "prefix" => prefix = true,
+                    "gte" => gte = true,
+                    "lte" => lte = true,
+                    "fulltext" => fulltext = true,
+                    "has_flags" => has_flags = true,
+                    "hstore_key" => hstore_key = true,
+                    "hstore_kv" => hstore_kv = true,
+                    _ => {
+                        return Err(syn::Error::new(
+                            kind.span(),
+                            "expected \"basic\", \"substring\", \"insensitive\", \"substring_insensitive\", \"lower\", \"contains\", \"overlaps\", \"prefix\", \"gte\", \"lte\", \"fulltext\", \"has_flags\", \"hstore_key\" or \"hstore_kv\"",
+                        ))
+                    }
+                }
+            } else if meta.path.is_ident("delimiter") {
+                opts.delimiter = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("proto") {
+                opts.proto = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("rename") {
+                opts.rename = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("deprecated") {
+                opts.deprecated = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("validate") {
+                let rules;
+                parenthesized!(rules in meta.input);
+                opts.validate = Some(rules.parse()?);
+            } else if meta.path.is_ident("max_values") {
+                let max: syn::LitInt = meta.value()?.parse()?;
+                opts.max_values = Some(max.base10_parse()?);
+            } else if meta.path.is_ident("chunk_size") {
+                let size: syn::LitInt = meta.value()?.parse()?;
+                opts.chunk_size = match size.base10_parse()? {
+                    0 => return Err(syn::Error::new(size.span(), "`chunk_size` is at least 1")),
+                    size => Some(size),
+                };
+            } else if meta.path.is_ident("overflow") {
+                let overflow: LitStr = meta.value()?.parse()?;
+                opts.truncate_values = match overflow.value().as_str() {
+                    "reject" => false,
+                    "truncate" => true,
+                    _ => {
+                        return Err(syn::Error::new(
+                            overflow.span(),
+                            "expected \"reject\" or \"truncate\"",
+                        ))
+                    }
+                };
+                overflow_set = true;
+            } else if meta.path.is_ident("tz") {
+                opts.tz = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("fts_table") {
+                opts.fts_table = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("exists_in") {
+                exists_in = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("not_exists_in") {
+                not_exists_in = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("count_in") {
+                count_in = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("on") {
+                on = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("column") {
+                column = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("op") {
+                opts.op = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("as") || meta.path.is_ident("ty") {
+                opts.as_ty = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+            } else if meta.path.is_ident("cfg") {
+                let predicate;
+                parenthesized!(predicate in meta.input);
+                opts.cfg = Some(predicate.parse()?);
+            } else if meta.path.is_ident("one_of") {
+                let values;
+                parenthesized!(values in meta.input);
+                opts.one_of = values
+                    .parse_terminated(|input| input.parse::<LitStr>(), Token![,])?
+                    .into_iter()
+                    .collect();
+                if opts.one_of.is_empty() {
+                    return Err(meta.error("`one_of` needs at least one value"));
+                }
+            } else {
+                return Err(unknown_option(
+                    &meta,
+                    &[
+                        "alias",
+                        "facet",
+                        "multiple",
+                        "max_values",
+                        "chunk_size",
+                        "overflow",
+                        "substring",
+                        "insensitive",
+                        "exact",
+                        "lower",
+                        "contains",
+                        "overlaps",
+                        "prefix",
+                        "gte",
+                        "lte",
+                        "fulltext",
+                        "has_flags",
+                        "hstore_key",
+                        "hstore_kv",
+                        "kind",
+                        "delimiter",
+                        "proto",
+                        "rename",
+                        "deprecated",
+                        "validate",
+                        "tz",
+                        "one_of",
+                        "fts_table",
+                        "exists_in",
+                        "not_exists_in",
+                        "count_in",
+                        "on",
+                        "column",
+                        "op",
+                        "as",
+                        "ty",
+                        "cfg",
+                    ],
+                ));
+            }
+            Ok(())
+        })?;
+
+        // `ILIKE` takes the values as patterns, `%` and `_` would match any character
+        if exact {
+            if substring || !insensitive {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`exact` compares `insensitive` filters with whole values, it can't go with `substring`",
+                ));
+            }
+            insensitive = false;
+            lower = true;
+        }
+        let kinds = [
+            substring || insensitive,
+            lower,
+            contains,
+            overlaps,
+            prefix,
+            gte,
+            lte,
+            fulltext,
+            has_flags,
+            hstore_key,
+            hstore_kv,
+        ];
+        if kinds.iter().filter(|kind| **kind).count() > 1 {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`lower`, `contains`, `overlaps`, `prefix`, `gte`, `lte`, `fulltext`, `has_flags`, `hstore_key` and `hstore_kv` can't be combined with another kind",
+            ));
+        }
+        if !opts.one_of.is_empty() && kinds.iter().any(|kind| *kind) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`one_of` filters compare whole values, they can't have a kind",
+            ));
+        }
+        if (gte || lte) && opts.multiple {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`gte` and `lte` compare a single value, they can't be `multiple`",
+            ));
+        }
+        if has_flags && (opts.multiple || opts.facet) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`has_flags` filters take a single mask of the required bits, they can't be `multiple` or facets",
+            ));
+        }
+        if opts.cfg.is_some() && opts.facet {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "facets are counted by a single query grouping all of their columns, they can't have a `cfg`",
+            ));
+        }
+        if (hstore_key || hstore_kv) && opts.facet {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "hstore columns can't be facets",
+            ));
+        }
+        if opts.max_values.is_some() && !(opts.multiple || contains || overlaps) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`max_values` limits the values of `multiple` filters",
+            ));
+        }
+        if opts.chunk_size.is_some() && opts.max_values.is_some() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`chunk_size` runs the values `max_values` would reject, keep one of them",
+            ));
+        }
+        if overflow_set && opts.max_values.is_none() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`overflow` is what happens past `max_values`, add it: `max_values = 200, overflow = \"truncate\"`",
+            ));
+        }
+        if hstore_kv && opts.multiple {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`hstore_kv` filters compare a single `key:value` pair, they can't be `multiple`",
+            ));
+        }
+        let mut related = vec![
+            (Related::Exists, exists_in),
+            (Related::NotExists, not_exists_in),
+            (Related::Count, count_in),
+        ]
+        .into_iter()
+        .filter_map(|(related, table)| Some((related, table?)))
+        .collect::<Vec<_>>();
+        if related.len() > 1 {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "a filter takes only one of `exists_in`, `not_exists_in` and `count_in`",
+            ));
+        }
+        opts.exists_in = match (related.pop(), on, column) {
+            (None, None, None) => None,
+            (Some((related @ Related::Exists, table)), Some(on), Some(column)) => Some(Subquery {
+                table,
+                on,
+                column: Some(column),
+                related,
+            }),
+            (Some((related @ Related::NotExists, table)), Some(on), column)
+            | (Some((related @ Related::Count, table)), Some(on), column @ None) => {
+                Some(Subquery {
+                    table,
+                    on,
+                    column,
+                    related,
+                })
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`exists_in`, `on` and `column` go together: `exists_in = \"comments\", on = \"post_id\", column = \"body\"`, `not_exists_in` takes `on` and an optional `column`, `count_in` takes `on`",
+                ))
+            }
+        };
+        if let Some(subquery) = &opts.exists_in {
+            if opts.rename.is_none() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`exists_in`, `not_exists_in` and `count_in` filters match related rows, name this one with `rename = \"...\"`",
+                ));
+            }
+            if opts.facet || opts.tz.is_some() || !opts.one_of.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "filters on related rows can't be facets or have a `tz` or `one_of`",
+                ));
+            }
+            let text = substring || insensitive || lower;
+            let other =
+                contains || overlaps || prefix || fulltext || has_flags || hstore_key || hstore_kv;
+            match (&subquery.related, &subquery.column) {
+                (Related::Count, _) if opts.multiple || text || other => {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "`count_in` compares the number of related rows with the `basic`, `gte` or `lte` kinds, it can't be `multiple`",
+                    ))
+                }
+                (Related::Count, _) => {}
+                (_, None) if opts.multiple || text || other || gte || lte => {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "`not_exists_in` without a `column` takes a boolean, it can't have a kind or be `multiple`",
+                    ))
+                }
+                (_, Some(_)) if other || gte || lte => {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "`exists_in` filters compare text with the `basic`, `substring`, `insensitive` or `lower` kinds",
+                    ))
+                }
+                _ => {}
+            }
+        }
+        if opts.op.is_some()
+            && (kinds.iter().any(|kind| *kind) || opts.exists_in.is_some() || opts.tz.is_some())
+        {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`op` filters compare the column with their registered op, they can't have a kind, a `tz` or related rows",
+            ));
+        }
+        if opts.as_ty.is_some() && opts.exists_in.is_some() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "filters on related rows compare the values of their own column, they can't have an `as` type",
+            ));
+        }
+        if opts.fts_table.is_some() && !fulltext {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`fts_table` is searched by `fulltext` filters",
+            ));
+        }
+        if fulltext && opts.multiple {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`fulltext` filters take a single query, they can't be `multiple`",
+            ));
+        }
+        opts.kind = match (substring, insensitive) {
+            _ if lower => FilterKind::Lower,
+            _ if contains => FilterKind::Contains,
+            _ if overlaps => FilterKind::Overlaps,
+            _ if prefix => FilterKind::Prefix,
+            _ if gte => FilterKind::Gte,
+            _ if lte => FilterKind::Lte,
+            _ if fulltext => FilterKind::Fulltext,
+            _ if has_flags => FilterKind::HasFlags,
+            _ if hstore_key => FilterKind::HstoreKey,
+            _ if hstore_kv => FilterKind::HstoreKv,
+            (true, true) => FilterKind::SubstrInsensitive,
+            (true, false) => FilterKind::Substr,
+            (false, true) => FilterKind::Insensitive,
+            (false, false) => FilterKind::Basic,
         };
+        // The rows of an equality match one value, the chunks load each row once
+        let equality =
+            matches!(opts.kind, FilterKind::Basic) && opts.exists_in.is_none() && opts.op.is_none();
+        if opts.chunk_size.is_some() && !(opts.multiple && equality) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`chunk_size` splits the values of `multiple` filters comparing with `=`",
+            ));
+        }
+        Ok(opts)
+    }
+}
 
-        Self {
-            multiple: matches(&meta, &["multiple"]),
-            kind,
+/// Whether the field is a `&str`, bare or in an `Option` or a `Vec`
+fn borrows_str(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(reference) => {
+            matches!(&*reference.elem, Type::Path(path) if path.path.is_ident("str"))
         }
+        Type::Path(path) => match path.path.segments.last() {
+            Some(segment) if segment.ident == "Option" || segment.ident == "Vec" => {
+                match &segment.arguments {
+                    syn::PathArguments::AngleBracketed(args) => args.args.iter().any(
+                        |arg| matches!(arg, syn::GenericArgument::Type(ty) if borrows_str(ty)),
+                    ),
+                    _ => false,
+                }
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Whether the field is a reference, bare or in an `Option`, which diesel can't load
+fn borrows(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(_) => true,
+        Type::Path(path) => match path.path.segments.last() {
+            Some(segment) if segment.ident == "Option" => match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) => args
+                    .args
+                    .iter()
+                    .any(|arg| matches!(arg, syn::GenericArgument::Type(ty) if borrows(ty))),
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
     }
 }
 
 impl From<&TypePath> for FilterableType {
     fn from(ty: &TypePath) -> Self {
-        match ty.to_token_stream().to_string().replace(' ', "").as_str() {
+        let ty = ty.to_token_stream().to_string().replace(' ', "");
+        // Borrowed strings are filtered with an owned `String`
+        let ty = ty
+            .strip_prefix("Option<")
+            .and_then(|ty| ty.strip_suffix('>'))
+            .unwrap_or(&ty);
+        // `bytea` columns are filtered with hex strings
+        if ty == "Vec<u8>" {
+            return Self::String;
+        }
+        // Array columns are filtered with values of their elements
+        let ty = ty
+            .strip_prefix("Vec<")
+            .and_then(|ty| ty.strip_suffix('>'))
+            .map(|ty| {
+                ty.strip_prefix("Option<")
+                    .and_then(|ty| ty.strip_suffix('>'))
+                    .unwrap_or(ty)
+            })
+            .unwrap_or(ty);
+        let ty = ty.trim_start_matches("std::borrow::");
+        match ty {
             "String" => Self::String,
             "Uuid" => Self::Uuid,
             "uuid::Uuid" => Self::Uuid,
-            "Option<String>" => Self::String,
-            "Option<Uuid>" => Self::Uuid,
-            "Option<uuid::Uuid>" => Self::Uuid,
+            cow if cow.starts_with("Cow<") && cow.ends_with(",str>") => Self::String,
             other => Self::Foreign(other.to_string()),
         }
     }
 }
 
-impl From<FilterableType> for Ident {
+// `Uuid` is named by its path, the model may not import it
+impl From<FilterableType> for Type {
     fn from(val: FilterableType) -> Self {
         match val {
-            FilterableType::String => Ident::new("String", Span::call_site()),
-            FilterableType::Uuid => Ident::new("Uuid", Span::call_site()),
-            FilterableType::Foreign(ty) => Ident::new(&ty, Span::call_site()),
+            FilterableType::String => parse_quote! { String },
+            FilterableType::Uuid => parse_quote! { ::uuid::Uuid },
+            FilterableType::Foreign(ty) => {
+                syn::parse_str(&ty).expect("the type of a field is a type")
+            }
         }
     }
 }
 
-struct TableName {
-    name: Ident,
+#[derive(Default)]
+struct DieselFilterOpts {
+    /// Seconds the rows of `filtered_cached` are cached for
+    cache_ttl: Option<syn::LitInt>,
+    /// `with_default_filters` merges the `DefaultFilters` of the filters struct
+    default_filters: bool,
+    frontend: Option<LitStr>,
+    /// `filter_sqlite` is generated along the Postgres queries, `backends(pg, sqlite)`
+    sqlite: bool,
+    /// `filter_mysql` is generated along the Postgres queries, `backends(pg, mysql)`
+    mysql: bool,
+    /// Column of the groups `filtered_latest` loads the newest row of
+    latest_per: Option<Ident>,
+    /// Column ordering the rows of a group, the newest last
+    latest_by: Option<Ident>,
+    lenient: bool,
+    /// Characters kept from the string binds logged by `filtered`, if logged
+    log_sql: Option<usize>,
+    /// Rows `filtered` loads at most, whatever the filters
+    max_rows: Option<syn::LitInt>,
+    /// Amount and currency columns bounded by the `min_amount`, `max_amount` and `currency` filters
+    money: Option<(Ident, Ident)>,
+    /// Module the generated items are emitted in
+    module: Option<Ident>,
+    /// Only the filters and the queries are generated, not the methods running them
+    no_loaders: bool,
+    proto: Option<Path>,
+    /// Postgres schema of the table when it isn't `public`
+    schema: Option<Ident>,
+    /// Module of the `table!` modules of bare table names, `crate::schema` by default
+    schema_module: Option<Path>,
+    /// Columns searched by the `search` filter
+    search: Vec<Ident>,
+    /// Timestamp column of the soft deleted rows, which are never matched
+    soft_delete: Option<Ident>,
+    /// `filtered` runs a statically typed query instead of the boxed one of `filter`
+    static_dispatch: bool,
+    /// `statement_timeout` of the queries of `filtered` and `count_filtered`
+    timeout_ms: Option<syn::LitInt>,
+    /// Tables `filtered_union` loads the rows of, with the columns of the table
+    union: Vec<Path>,
+    validate: bool,
 }
 
-impl Parse for TableName {
-    fn parse(input: ParseStream) -> syn::Result<Self> {
-        let attr_name: Ident = input.parse()?;
-        if attr_name != "table_name" {
-            return Err(syn::Error::new(attr_name.span(), "Wrong attribute name"));
+impl DieselFilterOpts {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut opts = Self::default();
+        for attr in attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("diesel_filter"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("backends") {
+                    let mut pg = false;
+                    meta.parse_nested_meta(|backend| {
+                        match () {
+                            _ if backend.path.is_ident("pg") => pg = true,
+                            _ if backend.path.is_ident("sqlite") => opts.sqlite = true,
+                            _ if backend.path.is_ident("mysql") => opts.mysql = true,
+                            _ => return Err(unknown_option(&backend, &["pg", "sqlite", "mysql"])),
+                        }
+                        Ok(())
+                    })?;
+                    if !pg {
+                        return Err(meta.error(
+                            "the loaders and the other queries are built for Postgres, keep `pg` in `backends`",
+                        ));
+                    }
+                } else if meta.path.is_ident("cache_ttl") {
+                    opts.cache_ttl = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("default_filters") {
+                    opts.default_filters = true;
+                } else if meta.path.is_ident("frontend") {
+                    opts.frontend = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("latest_per") {
+                    opts.latest_per = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else if meta.path.is_ident("by") {
+                    opts.latest_by = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else if meta.path.is_ident("lenient") {
+                    opts.lenient = true;
+                } else if meta.path.is_ident("log_sql") {
+                    if !cfg!(feature = "log") {
+                        return Err(meta.error(
+                            "`log_sql` logs through the `log` feature of diesel_filter, enable it",
+                        ));
+                    }
+                    opts.log_sql = match meta.input.peek(Token![=]) {
+                        true => {
+                            let mode: LitStr = meta.value()?.parse()?;
+                            match mode.value().as_str() {
+                                "redact" => Some(0),
+                                "truncate" => Some(8),
+                                _ => {
+                                    return Err(syn::Error::new(
+                                        mode.span(),
+                                        "log_sql must be \"redact\" or \"truncate\"",
+                                    ))
+                                }
+                            }
+                        }
+                        false => Some(0),
+                    };
+                } else if meta.path.is_ident("max_rows") {
+                    let max_rows: syn::LitInt = meta.value()?.parse()?;
+                    if max_rows.base10_parse::<u32>()? == 0 {
+                        return Err(syn::Error::new(
+                            max_rows.span(),
+                            "`max_rows` must be at least 1",
+                        ));
+                    }
+                    opts.max_rows = Some(max_rows);
+                } else if meta.path.is_ident("module") {
+                    opts.module = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else if meta.path.is_ident("money") {
+                    let (mut amount, mut currency) = (None, None);
+                    meta.parse_nested_meta(|column| {
+                        let slot = match () {
+                            _ if column.path.is_ident("amount") => &mut amount,
+                            _ if column.path.is_ident("currency") => &mut currency,
+                            _ => return Err(unknown_option(&column, &["amount", "currency"])),
+                        };
+                        let name: LitStr = column.value()?.parse()?;
+                        *slot = Some(Ident::new(&name.value(), name.span()));
+                        Ok(())
+                    })?;
+                    match (amount, currency) {
+                        (Some(amount), Some(currency)) => opts.money = Some((amount, currency)),
+                        _ => {
+                            return Err(meta.error(
+                                "expected the columns of both the amount and the currency: `money(amount = \"amount_cents\", currency = \"currency\")`",
+                            ))
+                        }
+                    }
+                } else if meta.path.is_ident("no_loaders") {
+                    opts.no_loaders = true;
+                } else if meta.path.is_ident("proto") {
+                    opts.proto = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else if meta.path.is_ident("schema") {
+                    opts.schema = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else if meta.path.is_ident("schema_module") {
+                    opts.schema_module = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else if meta.path.is_ident("search") {
+                    meta.parse_nested_meta(|column| match column.path.get_ident() {
+                        Some(column) => {
+                            opts.search.push(column.clone());
+                            Ok(())
+                        }
+                        None => Err(column.error("search columns must be column names")),
+                    })?;
+                } else if meta.path.is_ident("soft_delete") {
+                    opts.soft_delete = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else if meta.path.is_ident("static_dispatch") {
+                    opts.static_dispatch = true;
+                } else if meta.path.is_ident("timeout_ms") {
+                    let timeout: syn::LitInt = meta.value()?.parse()?;
+                    timeout.base10_parse::<u32>()?;
+                    opts.timeout_ms = Some(timeout);
+                } else if meta.path.is_ident("union") {
+                    meta.parse_nested_meta(|table| {
+                        opts.union.push(table.path.clone());
+                        Ok(())
+                    })?;
+                } else if meta.path.is_ident("validate") {
+                    opts.validate = true;
+                } else {
+                    return Err(unknown_option(
+                        &meta,
+                        &[
+                            "backends",
+                            "cache_ttl",
+                            "default_filters",
+                            "frontend",
+                            "latest_per",
+                            "by",
+                            "lenient",
+                            "log_sql",
+                            "max_rows",
+                            "module",
+                            "money",
+                            "no_loaders",
+                            "proto",
+                            "schema",
+                            "schema_module",
+                            "search",
+                            "soft_delete",
+                            "static_dispatch",
+                            "timeout_ms",
+                            "union",
+                            "validate",
+                        ],
+                    ));
+                }
+                Ok(())
+            })?;
+            if opts.latest_per.is_some() != opts.latest_by.is_some() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`latest_per` and `by` go together: `latest_per = \"device_id\", by = \"recorded_at\"`",
+                ));
+            }
+            if opts.no_loaders {
+                let loader_opts = [
+                    ("cache_ttl", opts.cache_ttl.is_some()),
+                    ("latest_per", opts.latest_per.is_some()),
+                    ("log_sql", opts.log_sql.is_some()),
+                    ("max_rows", opts.max_rows.is_some()),
+                    ("static_dispatch", opts.static_dispatch),
+                    ("timeout_ms", opts.timeout_ms.is_some()),
+                    ("union", !opts.union.is_empty()),
+                ];
+                if let Some((name, _)) = loader_opts.iter().find(|(_, set)| *set) {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        format!(
+                            "`{}` configures the generated loaders, remove it or `no_loaders`",
+                            name
+                        ),
+                    ));
+                }
+            }
         }
-        input.parse::<Token![=]>()?;
-        let name: Ident = input.parse()?;
-        Ok(TableName { name })
+        Ok(opts)
     }
 }
 
-#[proc_macro_derive(DieselFilter, attributes(filter, table_name, pagination))]
-pub fn filter(input: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(input as DeriveInput);
+/// The `%value%` pattern of `value`, wrapped in `%` by Postgres so the SQL is the
+/// same whatever the value and the bound value is the one of the filter
+fn substring_pattern(value: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote! {
+        ::diesel::dsl::sql::<::diesel::sql_types::Text>("('%' || ")
+            .bind::<::diesel::sql_types::Text, _>(#value)
+            .sql(" || '%')")
+    }
+}
 
-    let table_name = match input
-        .attrs
-        .iter()
-        .filter(|attr| attr.path.is_ident("diesel"))
-        .filter_map(|a| a.parse_args::<TableName>().ok())
-        .next()
-    {
-        Some(tn) => tn.name,
-        None => panic!("please provide #[diesel(table_name = ...)] attribute"),
-    };
+/// The `%value%` pattern of `value` for MySQL, whose `||` is a logical or
+fn concat_pattern(value: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote! {
+        ::diesel::dsl::sql::<::diesel::sql_types::Text>("CONCAT('%', ")
+            .bind::<::diesel::sql_types::Text, _>(#value)
+            .sql(", '%')")
+    }
+}
 
-    let pagination = input
-        .attrs
-        .iter()
-        .filter(|m| m.path.is_ident("pagination"))
-        .last()
-        .is_some();
+/// The `%value%` patterns of `values`, bound as is and wrapped in `%` by
+/// Postgres rather than formatting a pattern for each of them on every call
+fn substring_patterns(values: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote! {
+        ::diesel::dsl::sql::<::diesel::sql_types::Array<::diesel::sql_types::Text>>(
+            "ARRAY(SELECT '%' || value || '%' FROM unnest(",
+        )
+        .bind::<::diesel::sql_types::Array<::diesel::sql_types::Text>, _>(#values)
+        .sql(") AS value)")
+    }
+}
 
-    let struct_name = input.ident;
-    let mut filters = vec![];
+/// `values` lowercased by Postgres, like the column they are compared to
+fn lower_values(values: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote! {
+        ::diesel::dsl::sql::<::diesel::sql_types::Array<::diesel::sql_types::Text>>(
+            "ARRAY(SELECT lower(value) FROM unnest(",
+        )
+        .bind::<::diesel::sql_types::Array<::diesel::sql_types::Text>, _>(#values)
+        .sql(") AS value)")
+    }
+}
 
-    if let Data::Struct(data) = input.data {
-        if let Fields::Named(fields) = data.fields {
-            for field in fields.named {
-                match field.ident {
-                    Some(name) => {
-                        let field_type = field.ty;
-                        for attr in field.attrs.into_iter() {
-                            if !attr.path.is_ident("filter") {
-                                continue;
-                            }
-                            let opts = match attr.parse_meta().unwrap() {
-                                Meta::List(te) => {
-                                    FilterOpts::from(te.nested.into_iter().collect::<Vec<_>>())
-                                }
-                                Meta::Path(_) => FilterOpts::default(),
-                                _ => continue,
-                            };
+/// The hex `value` decoded by Postgres, typed like the `bytea` column it is compared to
+fn hex_value(
+    value: proc_macro2::TokenStream,
+    column: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote! {
+        ::diesel::dsl::sql::<::diesel::dsl::SqlTypeOf<#column>>("decode(")
+            .bind::<::diesel::sql_types::Nullable<::diesel::sql_types::Text>, _>(#value)
+            .sql(", 'hex')")
+    }
+}
+
+/// The `value` cast to an `interval` by Postgres, e.g `1 hour` or `PT1H`
+fn interval_value(
+    value: proc_macro2::TokenStream,
+    column: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote! {
+        ::diesel::dsl::sql::<::diesel::dsl::SqlTypeOf<#column>>("CAST(")
+            .bind::<::diesel::sql_types::Nullable<::diesel::sql_types::Text>, _>(#value)
+            .sql(" AS interval)")
+    }
+}
 
-                            if let Type::Path(ty) = &field_type {
-                                let ty = FilterableType::from(ty);
-                                let name = name.clone();
+/// The `values` cast to intervals by Postgres
+fn interval_values(
+    values: proc_macro2::TokenStream,
+    column: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote! {
+        ::diesel::dsl::sql::<::diesel::sql_types::Array<::diesel::dsl::SqlTypeOf<#column>>>("CAST(")
+            .bind::<::diesel::sql_types::Array<::diesel::sql_types::Text>, _>(#values)
+            .sql(" AS interval[])")
+    }
+}
 
-                                filters.push(Filter { name, ty, opts });
-                                continue;
-                            }
-                            panic!("this type is not supported");
-                        }
-                    }
-                    None => continue,
-                }
-            }
-        }
+/// The hex `values` decoded by Postgres
+fn hex_values(
+    values: proc_macro2::TokenStream,
+    column: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote! {
+        ::diesel::dsl::sql::<::diesel::sql_types::Array<::diesel::dsl::SqlTypeOf<#column>>>(
+            "ARRAY(SELECT decode(value, 'hex') FROM unnest(",
+        )
+        .bind::<::diesel::sql_types::Array<::diesel::sql_types::Text>, _>(#values)
+        .sql(") AS value)")
     }
+}
 
-    let filter_struct_ident = Ident::new(&format!("{}Filters", struct_name), struct_name.span());
+/// `bytea` columns starting with the hex `value`, or one of the hex `values`.
+/// diesel has no `position(... in ...)`, the column is written out
+fn hex_prefix(
+    value: proc_macro2::TokenStream,
+    column_sql: &str,
+    multiple: bool,
+) -> proc_macro2::TokenStream {
+    let (start, end, bind_ty) = match multiple {
+        false => (
+            "position(decode(".to_owned(),
+            format!(", 'hex') in {}) = 1", column_sql),
+            quote! { ::diesel::sql_types::Nullable<::diesel::sql_types::Text> },
+        ),
+        true => (
+            "EXISTS (SELECT FROM unnest(".to_owned(),
+            format!(
+                ") AS value WHERE position(decode(value, 'hex') in {}) = 1)",
+                column_sql
+            ),
+            quote! { ::diesel::sql_types::Array<::diesel::sql_types::Text> },
+        ),
+    };
+    quote! {
+        ::diesel::dsl::sql::<::diesel::sql_types::Bool>(#start)
+            .bind::<#bind_ty, _>(#value)
+            .sql(#end)
+    }
+}
 
-    if filters.is_empty() {
-        panic!("please annotate at least one field to filter with #[filter] on your struct");
+/// Bitmask columns with all the bits of `mask` set. diesel has no bitwise
+/// operators, the mask is bound on both sides of the comparison
+fn has_flags(
+    mask: proc_macro2::TokenStream,
+    column: &proc_macro2::TokenStream,
+    column_sql: &str,
+) -> proc_macro2::TokenStream {
+    let start = format!("({} & ", column_sql);
+    quote! {
+        ::diesel::dsl::sql::<::diesel::sql_types::Bool>(#start)
+            .bind::<::diesel::sql_types::Nullable<::diesel::dsl::SqlTypeOf<#column>>, _>(#mask)
+            .sql(") = ")
+            .bind::<::diesel::sql_types::Nullable<::diesel::dsl::SqlTypeOf<#column>>, _>(#mask)
     }
+}
 
-    let mut fields = vec![];
-    let mut queries = vec![];
-    let mut uses = vec![];
-    let mut has_multiple = false;
-    for filter in filters {
-        let field = filter.name;
-        let ty: Ident = filter.ty.into();
-        let opts = filter.opts;
+/// `hstore` columns with the `key`, or with any of the `keys` when `multiple`
+fn hstore_key(
+    key: proc_macro2::TokenStream,
+    column_sql: &str,
+    multiple: bool,
+) -> proc_macro2::TokenStream {
+    let (operator, bind_ty) = match multiple {
+        false => (
+            "?",
+            quote! { ::diesel::sql_types::Nullable<::diesel::sql_types::Text> },
+        ),
+        true => (
+            "?|",
+            quote! { ::diesel::sql_types::Array<::diesel::sql_types::Text> },
+        ),
+    };
+    let start = format!("{} {} ", column_sql, operator);
+    quote! {
+        ::diesel::dsl::sql::<::diesel::sql_types::Bool>(#start).bind::<#bind_ty, _>(#key)
+    }
+}
 
-        let q = if opts.multiple {
-            has_multiple = true;
-            #[cfg(feature = "rocket")]
-            fields.push(quote! {
-                #[field(default = Option::None)]
-                pub #field: Option<Vec<#ty>>,
-            });
-            #[cfg(not(feature = "rocket"))]
-            fields.push(quote! {
-                pub #field: Option<Vec<#ty>>,
-            });
-            match opts.kind {
-                FilterKind::Basic => {
-                    quote! { #table_name::#field.eq(any(filter)) }
-                }
-                FilterKind::Substr => {
-                    quote! {
-                        #table_name::#field.like(any(
-                            filter.iter().map(|f| format!("%{}%", f)).collect::<Vec<_>>()
-                        ))
-                    }
-                }
-                FilterKind::Insensitive => {
-                    quote! { #table_name::#field.ilike(any(filter)) }
-                }
-                FilterKind::SubstrInsensitive => {
-                    quote! {
-                        #table_name::#field.ilike(any(
-                            filter.iter().map(|f| format!("%{}%", f)).collect::<Vec<_>>()
-                        ))
-                    }
-                }
+/// `hstore` columns with the value of a `key:value` `pair` at its key. A pair
+/// without `:` binds `NULL`s and matches no rows
+fn hstore_kv(pair: proc_macro2::TokenStream, column_sql: &str) -> proc_macro2::TokenStream {
+    let start = format!("({} -> ", column_sql);
+    quote! {
+        {
+            let pair = #pair.and_then(|pair: &str| pair.split_once(':'));
+            ::diesel::dsl::sql::<::diesel::sql_types::Bool>(#start)
+                .bind::<::diesel::sql_types::Nullable<::diesel::sql_types::Text>, _>(pair.map(|(key, _)| key))
+                .sql(") = ")
+                .bind::<::diesel::sql_types::Nullable<::diesel::sql_types::Text>, _>(pair.map(|(_, value)| value))
+        }
+    }
+}
+
+/// A template of the raw conditions with its placeholders numbered from `$1`:
+/// `{}` takes the next number and `{0}` the first one again
+fn numbered_sql(template: &str) -> String {
+    let mut sql = String::new();
+    let mut next = 1;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                sql.push(c);
             }
-        } else {
-            fields.push(quote! {
-                pub #field: Option<#ty>,
-            });
-            match opts.kind {
-                FilterKind::Basic => {
-                    quote! { #table_name::#field.eq(filter) }
-                }
-                FilterKind::Substr => {
-                    quote! { #table_name::#field.like(format!("%{}%", filter)) }
-                }
-                FilterKind::Insensitive => {
-                    quote! { #table_name::#field.ilike(filter) }
-                }
-                FilterKind::SubstrInsensitive => {
-                    quote! { #table_name::#field.ilike(format!("%{}%", filter)) }
+            ('{', _) => {
+                let position = chars.by_ref().take_while(|c| *c != '}').collect::<String>();
+                match position.parse::<usize>() {
+                    Ok(position) => sql.push_str(&(position + 1).to_string()),
+                    Err(_) => {
+                        sql.push_str(&next.to_string());
+                        next += 1;
+                    }
                 }
             }
-        };
-
-        queries.push(quote! {
-            if let Some(ref filter) = filters.#field {
-                query = query.filter(#q);
-            }
-        });
+            _ => sql.push(c),
+        }
     }
+    sql
+}
 
-    if has_multiple {
-        uses.push(quote! { use diesel::dsl::any; })
-    }
-    if pagination {
-        fields.push(quote! {
-            pub page: Option<i64>,
-            pub per_page: Option<i64>,
-        });
+/// Text columns matching the full-text `query`, written with the web search
+/// syntax (`"quoted phrase" or -excluded`). diesel has no text search, the
+/// condition is written out around the query by `fulltext_sql`
+fn fulltext(
+    query: proc_macro2::TokenStream,
+    (start, end): &(String, String),
+) -> proc_macro2::TokenStream {
+    quote! {
+        ::diesel::dsl::sql::<::diesel::sql_types::Bool>(#start)
+            .bind::<::diesel::sql_types::Nullable<::diesel::sql_types::Text>, _>(#query)
+            .sql(#end)
     }
+}
 
-    #[cfg(feature = "rocket")]
-    let filters_struct = quote! {
-        #[derive(FromForm, Debug)]
-        pub struct #filter_struct_ident {
-            #( #fields )*
+/// The SQL before and after the query of a `fulltext` filter. With a `fts_table`
+/// the rows are kept when their `key` is in the rows of the table whose `tsvector`
+/// column, named like the filtered one, matches the query
+fn fulltext_sql(
+    column: &Ident,
+    column_sql: &str,
+    fts_table: Option<&str>,
+    key: Option<(&Ident, String)>,
+) -> syn::Result<(String, String)> {
+    let (table, (key, key_sql)) = match (fts_table, key) {
+        (None, _) => {
+            return Ok((
+                format!("to_tsvector({}) @@ websearch_to_tsquery(", column_sql),
+                ")".to_owned(),
+            ))
+        }
+        (Some(table), Some(key)) => (table, key),
+        (Some(_), None) => {
+            return Err(syn::Error::new(
+                column.span(),
+                "`fts_table` is joined on the primary key, the model needs a single column key among its fields",
+            ))
         }
     };
+    let name = |ident: &Ident| ident.to_string().trim_start_matches("r#").to_owned();
+    Ok((
+        format!(
+            "{} IN (SELECT {}.\"{}\" FROM {} WHERE {}.\"{}\" @@ websearch_to_tsquery(",
+            key_sql,
+            table,
+            name(key),
+            table,
+            table,
+            name(column)
+        ),
+        "))".to_owned(),
+    ))
+}
 
-    #[cfg(any(feature = "actix", feature = "axum"))]
-    let filters_struct = quote! {
-        #[derive(serde::Deserialize, Debug)]
-        pub struct #filter_struct_ident {
-            #( #fields )*
+/// The SQL before and after the value of an `exists_in` filter, the related rows
+/// of `parent_sql` whose column matches the value, or one of the values, and the
+/// type the value is bound with
+fn subquery_sql(
+    subquery: &Subquery,
+    parent_sql: &str,
+    kind: &FilterKind,
+    multiple: bool,
+) -> (String, String, proc_macro2::TokenStream) {
+    let Subquery {
+        table,
+        on,
+        column,
+        related,
+    } = subquery;
+    let condition = format!("FROM {} WHERE {}.\"{}\" = {}", table, table, on, parent_sql);
+    if let Related::Count = related {
+        let operator = match kind {
+            FilterKind::Gte => ">=",
+            FilterKind::Lte => "<=",
+            _ => "=",
+        };
+        return (
+            format!("(SELECT count(*) {}) {} ", condition, operator),
+            String::new(),
+            quote! { ::diesel::sql_types::Nullable<::diesel::sql_types::BigInt> },
+        );
+    }
+    let related_rows = format!("SELECT 1 {}", condition);
+    // `true` keeps the rows without related rows
+    let column = match column {
+        Some(column) => format!("{}.\"{}\"", table, column),
+        None => {
+            return (
+                format!("EXISTS ({}) <> ", related_rows),
+                String::new(),
+                quote! { ::diesel::sql_types::Nullable<::diesel::sql_types::Bool> },
+            )
         }
     };
-
-    #[cfg(not(any(feature = "rocket", feature = "actix", feature = "axum")))]
-    let filters_struct = quote! {
-        #[derive(Debug)]
-        pub struct #filter_struct_ident {
-            #( #fields )*
+    let (start, end) = match (multiple, kind) {
+        (false, FilterKind::Substr) => (format!("{} LIKE ('%' || ", column), " || '%')"),
+        (false, FilterKind::Insensitive) => (format!("{} ILIKE ", column), ""),
+        (false, FilterKind::SubstrInsensitive) => {
+            (format!("{} ILIKE ('%' || ", column), " || '%')")
         }
+        (false, FilterKind::Lower) => (format!("lower({}) = lower(", column), ")"),
+        (false, _) => (format!("{} = ", column), ""),
+        (true, FilterKind::Substr) => (
+            format!(
+                "{} LIKE ANY(ARRAY(SELECT '%' || value || '%' FROM unnest(",
+                column
+            ),
+            ") AS value))",
+        ),
+        (true, FilterKind::Insensitive) => (format!("{} ILIKE ANY(", column), ")"),
+        (true, FilterKind::SubstrInsensitive) => (
+            format!(
+                "{} ILIKE ANY(ARRAY(SELECT '%' || value || '%' FROM unnest(",
+                column
+            ),
+            ") AS value))",
+        ),
+        (true, FilterKind::Lower) => (
+            format!(
+                "lower({}) = ANY(ARRAY(SELECT lower(value) FROM unnest(",
+                column
+            ),
+            ") AS value))",
+        ),
+        (true, _) => (format!("{} = ANY(", column), ")"),
+    };
+    let bind_ty = match multiple {
+        true => quote! { ::diesel::sql_types::Array<::diesel::sql_types::Text> },
+        false => quote! { ::diesel::sql_types::Nullable<::diesel::sql_types::Text> },
     };
+    let not = match related {
+        Related::NotExists => "NOT ",
+        Related::Exists | Related::Count => "",
+    };
+    (
+        format!("{}EXISTS ({} AND {}", not, related_rows, start),
+        format!("{})", end),
+        bind_ty,
+    )
+}
 
-    let expanded = match pagination {
-        true => {
-            quote! {
-                #filters_struct
+/// Rows with related rows matching the `value`, or one of the `values`
+fn subquery(
+    value: proc_macro2::TokenStream,
+    (start, end, bind_ty): &(String, String, proc_macro2::TokenStream),
+) -> proc_macro2::TokenStream {
+    quote! {
+        ::diesel::dsl::sql::<::diesel::sql_types::Bool>(#start)
+            .bind::<#bind_ty, _>(#value)
+            .sql(#end)
+    }
+}
 
-                impl #struct_name {
-                    pub fn filtered(filters: &#filter_struct_ident, conn: &mut PgConnection) -> Result<(Vec<#struct_name>, i64), diesel::result::Error> {
-                        Self::filter(filters)
-                          .paginate(filters.page)
-                          .per_page(filters.per_page)
-                          .load_and_count::<#struct_name>(conn)
-                    }
+/// The date of `column_sql` in the time zone `tz` (`UTC` when `NULL`) compared to
+/// the `date`, or one of the `dates`. Timestamps without time zone are in UTC
+fn local_date(
+    date: proc_macro2::TokenStream,
+    tz: proc_macro2::TokenStream,
+    column_sql: &str,
+    with_time_zone: bool,
+    multiple: bool,
+) -> proc_macro2::TokenStream {
+    let column_sql = match with_time_zone {
+        true => column_sql.to_owned(),
+        false => format!("timezone('UTC', {})", column_sql),
+    };
+    let (middle, end, date_ty) = match multiple {
+        false => (
+            format!(", 'UTC'), {}) AS date) = CAST(", column_sql),
+            " AS date)",
+            quote! { ::diesel::sql_types::Nullable<::diesel::sql_types::Text> },
+        ),
+        true => (
+            format!(", 'UTC'), {}) AS date) = ANY(CAST(", column_sql),
+            " AS date[]))",
+            quote! { ::diesel::sql_types::Array<::diesel::sql_types::Text> },
+        ),
+    };
+    quote! {
+        ::diesel::dsl::sql::<::diesel::sql_types::Bool>("CAST(timezone(COALESCE(")
+            .bind::<::diesel::sql_types::Nullable<::diesel::sql_types::Text>, _>(#tz)
+            .sql(#middle)
+            .bind::<#date_ty, _>(#date)
+            .sql(#end)
+    }
+}
 
-                    pub fn filter<'a>(filters: &'a #filter_struct_ident) -> crate::schema::#table_name::BoxedQuery<'a, diesel::pg::Pg> {
-                        #( #uses )*
-                        let mut query = crate::schema::#table_name::table.into_boxed();
+/// The enum of the values of a `one_of` filter, bound as text like the strings
+/// it replaces and parsed by the frameworks, which reject the other values
+fn one_of_enum(
+    ident: &Ident,
+    values: &[LitStr],
+    frontends: Frontends,
+    serde: bool,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let variants = values
+        .iter()
+        .map(variant_name)
+        .collect::<syn::Result<Vec<_>>>()?;
+    for (i, variant) in variants.iter().enumerate() {
+        if variants[..i].contains(variant) {
+            return Err(syn::Error::new(
+                values[i].span(),
+                format!(
+                    "`one_of` values must be distinct, `{}` is repeated",
+                    variant
+                ),
+            ));
+        }
+    }
 
-                        #( #queries )*
+    let mut derives = vec![
+        quote! { ::std::fmt::Debug },
+        quote! { ::std::clone::Clone },
+        quote! { ::std::marker::Copy },
+        quote! { ::std::cmp::PartialEq },
+        quote! { ::std::cmp::Eq },
+        quote! { ::std::hash::Hash },
+        quote! { ::diesel::AsExpression },
+        quote! { ::diesel::FromSqlRow },
+    ];
+    let mut variant_attrs = values.iter().map(|_| vec![]).collect::<Vec<_>>();
+    if serde {
+        derives.push(quote! { ::serde::Deserialize });
+        derives.push(quote! { ::serde::Serialize });
+        for (attrs, value) in variant_attrs.iter_mut().zip(values) {
+            attrs.push(quote! { #[serde(rename = #value)] });
+        }
+    }
+    if frontends.aide {
+        derives.push(quote! { ::schemars::JsonSchema });
+    }
+    if frontends.poem {
+        derives.push(quote! { ::poem_openapi::Enum });
+        for (attrs, value) in variant_attrs.iter_mut().zip(values) {
+            attrs.push(quote! { #[oai(rename = #value)] });
+        }
+    }
+    if frontends.salvo {
+        derives.push(quote! { ::salvo::oapi::ToSchema });
+    }
+    if frontends.juniper {
+        derives.push(quote! { ::juniper::GraphQLEnum });
+        for (attrs, value) in variant_attrs.iter_mut().zip(values) {
+            attrs.push(quote! { #[graphql(name = #value)] });
+        }
+    }
+    if cfg!(feature = "ts") {
+        derives.push(quote! { ::ts_rs::TS });
+        for (attrs, value) in variant_attrs.iter_mut().zip(values) {
+            attrs.push(quote! { #[ts(rename = #value)] });
+        }
+    }
+    let rocket_impl = match frontends.rocket {
+        true => quote! {
+            impl<'v> ::rocket::form::FromFormField<'v> for #ident {
+                fn from_value(field: ::rocket::form::ValueField<'v>) -> ::rocket::form::Result<'v, Self> {
+                    field.value.parse().map_err(|err: ::diesel_filter::one_of::OneOfError| {
+                        ::rocket::form::Error::validation(err.to_string()).into()
+                    })
+                }
+            }
+        },
+        false => quote! {},
+    };
+    let doc = format!(
+        "The values accepted by the filter: {}",
+        values
+            .iter()
+            .map(|value| format!("`{}`", value.value()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
 
-                        query
-                    }
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(#( #derives ),*)]
+        #[diesel(sql_type = ::diesel::sql_types::Text)]
+        pub enum #ident {
+            #( #( #variant_attrs )* #variants, )*
+        }
+
+        impl #ident {
+            pub const VALUES: &'static [&'static str] = &[#( #values ),*];
+
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    #( #ident::#variants => #values, )*
                 }
             }
         }
-        false => {
-            quote! {
-                #filters_struct
 
-                impl #struct_name {
-                    pub fn filtered(filters: &#filter_struct_ident, conn: &mut PgConnection) -> Result<Vec<#struct_name>, diesel::result::Error> {
-                        Self::filter(filters).load::<#struct_name>(conn)
-                    }
+        impl ::std::str::FromStr for #ident {
+            type Err = ::diesel_filter::one_of::OneOfError;
 
-                    pub fn filter<'a>(filters: &'a #filter_struct_ident) -> crate::schema::#table_name::BoxedQuery<'a, diesel::pg::Pg> {
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    #( #values => Ok(#ident::#variants), )*
+                    _ => Err(::diesel_filter::one_of::OneOfError {
+                        value: s.to_owned(),
+                        expected: Self::VALUES,
+                    }),
+                }
+            }
+        }
+
+        impl ::std::fmt::Display for #ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl ::diesel::serialize::ToSql<::diesel::sql_types::Text, ::diesel::pg::Pg> for #ident {
+            fn to_sql<'b>(
+                &'b self,
+                out: &mut ::diesel::serialize::Output<'b, '_, ::diesel::pg::Pg>,
+            ) -> ::diesel::serialize::Result {
+                <str as ::diesel::serialize::ToSql<::diesel::sql_types::Text, ::diesel::pg::Pg>>::to_sql(
+                    self.as_str(),
+                    out,
+                )
+            }
+        }
+
+        impl ::diesel::deserialize::FromSql<::diesel::sql_types::Text, ::diesel::pg::Pg> for #ident {
+            fn from_sql(bytes: ::diesel::pg::PgValue<'_>) -> ::diesel::deserialize::Result<Self> {
+                let value = <String as ::diesel::deserialize::FromSql<
+                    ::diesel::sql_types::Text,
+                    ::diesel::pg::Pg,
+                >>::from_sql(bytes)?;
+                Ok(value.parse()?)
+            }
+        }
+
+        #rocket_impl
+    })
+}
+
+/// `in-progress` -> `InProgress`, the variant of a `one_of` value
+fn variant_name(value: &LitStr) -> syn::Result<Ident> {
+    let name = value
+        .value()
+        .split(|c: char| !c.is_alphanumeric())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect::<String>();
+    match name.chars().next() {
+        Some(first) if first.is_alphabetic() => Ok(Ident::new(&name, value.span())),
+        _ => Err(syn::Error::new(
+            value.span(),
+            "`one_of` values must start with a letter",
+        )),
+    }
+}
+
+/// `owner_id` -> `OwnerId`
+fn camel_case(name: &str) -> String {
+    name.trim_start_matches("r#")
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Skips the value of the other options of `#[diesel(...)]`, an expression, a type
+/// like `deserialize_as = Option<String>`, or a list
+fn skip_diesel_option(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        let value = meta.value()?;
+        let ty = value.fork();
+        match ty.parse::<Type>().is_ok() && (ty.is_empty() || ty.peek(Token![,])) {
+            true => drop(value.parse::<Type>()?),
+            false => drop(value.parse::<Expr>()?),
+        }
+    } else if meta.input.peek(token::Paren) {
+        let skipped;
+        parenthesized!(skipped in meta.input);
+        skipped.parse::<proc_macro2::TokenStream>()?;
+    }
+    Ok(())
+}
+
+/// The `name = ...` option of `#[diesel(...)]`, other diesel options are skipped
+fn diesel_option<T: Parse>(attrs: &[Attribute], name: &str) -> syn::Result<Option<T>> {
+    let mut value = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("diesel")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(name) {
+                value = Some(meta.value()?.parse()?);
+            } else {
+                skip_diesel_option(&meta)?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(value)
+}
+
+/// `#[pagination(default_page = 1, default_per_page = 20)]`
+#[derive(Default)]
+struct PaginationOpts {
+    default_page: Option<syn::LitInt>,
+    default_per_page: Option<syn::LitInt>,
+}
+
+impl PaginationOpts {
+    /// `None` when the struct isn't paginated
+    fn parse(attrs: &[Attribute]) -> syn::Result<Option<Self>> {
+        let mut pagination = None;
+        for attr in attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("pagination"))
+        {
+            let opts = pagination.get_or_insert_with(Self::default);
+            if let Meta::Path(_) = attr.meta {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("default_page") {
+                    opts.default_page = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("default_per_page") {
+                    opts.default_per_page = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(unknown_option(&meta, &["default_page", "default_per_page"]));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(pagination)
+    }
+}
+
+/// `#[sortable]` on a column, sorted by under the name of the field or `rename`,
+/// `#[sortable(join = "users", columns(name))]` on a foreign key for the columns
+/// of the referenced rows, sorted by as `users.name`, and
+/// `#[sortable(distance_from(lat = "lat", lng = "lng"))]` on the struct for the
+/// distance of the rows to the `near_lat`/`near_lng` point, sorted by as `distance`
+#[derive(Default)]
+struct SortOpts {
+    rename: Option<String>,
+    join: Option<String>,
+    /// Column of `join` the field references, `id` by default
+    on: Option<String>,
+    columns: Vec<Ident>,
+    /// `First` or `Last`, where the `NULL`s go unless the request says otherwise
+    nulls: Option<Ident>,
+    /// Latitude and longitude columns, in degrees
+    distance_from: Option<(Ident, Ident)>,
+}
+
+impl SortOpts {
+    fn parse(attr: &Attribute) -> syn::Result<Self> {
+        let mut opts = Self::default();
+        if let Meta::Path(_) = attr.meta {
+            return Ok(opts);
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                opts.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("join") {
+                opts.join = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("on") {
+                opts.on = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("columns") {
+                meta.parse_nested_meta(|column| {
+                    opts.columns.push(column.path.require_ident()?.clone());
+                    Ok(())
+                })?;
+            } else if meta.path.is_ident("nulls") {
+                let nulls: LitStr = meta.value()?.parse()?;
+                let order = match nulls.value().as_str() {
+                    "first" => "First",
+                    "last" => "Last",
+                    _ => {
+                        return Err(syn::Error::new(
+                            nulls.span(),
+                            "expected \"first\" or \"last\"",
+                        ))
+                    }
+                };
+                opts.nulls = Some(Ident::new(order, nulls.span()));
+            } else if meta.path.is_ident("distance_from") {
+                let (mut lat, mut lng) = (None, None);
+                meta.parse_nested_meta(|column| {
+                    let slot = match () {
+                        _ if column.path.is_ident("lat") => &mut lat,
+                        _ if column.path.is_ident("lng") => &mut lng,
+                        _ => return Err(unknown_option(&column, &["lat", "lng"])),
+                    };
+                    let name: LitStr = column.value()?.parse()?;
+                    *slot = Some(Ident::new(&name.value(), name.span()));
+                    Ok(())
+                })?;
+                match (lat, lng) {
+                    (Some(lat), Some(lng)) => opts.distance_from = Some((lat, lng)),
+                    _ => {
+                        return Err(meta.error(
+                            "expected the columns of both coordinates: `distance_from(lat = \"lat\", lng = \"lng\")`",
+                        ))
+                    }
+                }
+            } else {
+                return Err(unknown_option(
+                    &meta,
+                    &["rename", "join", "on", "columns", "nulls", "distance_from"],
+                ));
+            }
+            Ok(())
+        })?;
+        if opts.distance_from.is_some() {
+            if opts.join.is_some() || !opts.columns.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`distance_from` is sorted by the columns of the table, remove `join` and `columns`",
+                ));
+            }
+            return Ok(opts);
+        }
+        if opts.join.is_some() == opts.columns.is_empty()
+            || (opts.on.is_some() && opts.join.is_none())
+        {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`join`, `columns` and the optional `on` go together: `join = \"users\", columns(name, email)`",
+            ));
+        }
+        Ok(opts)
+    }
+}
+
+/// A key of the `sort` parameter
+struct Sortable {
+    key: String,
+    /// Column of the field, the latitude for a distance
+    column: Ident,
+    /// The field of the column, the cursors of `filtered_after` hold its value
+    field: Ident,
+    /// Whether the field is an `Option`
+    nullable: bool,
+    by: SortBy,
+    nulls: Option<Ident>,
+}
+
+enum SortBy {
+    Column,
+    /// Table and column the field references, and the column of the referenced
+    /// rows sorted by
+    Join(String, String, Ident),
+    /// Longitude column, the distance to the `near_lat`/`near_lng` point is sorted by
+    Distance(Ident),
+}
+
+/// The fields of `#[diesel(primary_key(...))]`, `id` by default like diesel
+fn diesel_primary_key(attrs: &[Attribute]) -> syn::Result<Vec<Ident>> {
+    let mut fields = vec![];
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("diesel")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("primary_key") {
+                meta.parse_nested_meta(|field| {
+                    fields.push(field.path.require_ident()?.clone());
+                    Ok(())
+                })?;
+            } else {
+                skip_diesel_option(&meta)?;
+            }
+            Ok(())
+        })?;
+    }
+    if fields.is_empty() {
+        fields.push(Ident::new("id", Span::call_site()));
+    }
+    Ok(fields)
+}
+
+/// `column_name = full_name` or the older `column_name = "full_name"`
+struct ColumnName(Ident);
+
+impl Parse for ColumnName {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        match input.peek(LitStr) {
+            true => Ok(Self(input.parse::<LitStr>()?.parse()?)),
+            false => Ok(Self(input.parse()?)),
+        }
+    }
+}
+
+#[proc_macro_derive(
+    DieselFilter,
+    attributes(filter, table_name, pagination, diesel_filter, garde, sortable)
+)]
+pub fn filter(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Tests of the filters of a model against its schema, for the test suite of the
+/// crate: every filter set alone filters on its column, every sort key orders
+/// the rows and the pages are offset as expected.
+///
+/// ```text
+/// diesel_filter_tests!(Project);
+/// ```
+///
+/// The tests are emitted in a `project_filter_tests` module built with the tests only.
+#[proc_macro]
+pub fn diesel_filter_tests(input: TokenStream) -> TokenStream {
+    let model = parse_macro_input!(input as Path);
+    let name = &model.segments.last().unwrap().ident;
+    let mut module = String::new();
+    for (i, c) in name
+        .to_string()
+        .trim_start_matches("r#")
+        .chars()
+        .enumerate()
+    {
+        if c.is_uppercase() && i > 0 {
+            module.push('_');
+        }
+        module.extend(c.to_lowercase());
+    }
+    let module = Ident::new(&format!("{}_filter_tests", module), name.span());
+    quote! {
+        #[cfg(test)]
+        mod #module {
+            use super::*;
+
+            #[test]
+            fn filters_filter_their_column() {
+                #model::__diesel_filter_test_filters();
+            }
+
+            #[test]
+            fn sort_keys_order_the_rows() {
+                #model::__diesel_filter_test_sorts();
+            }
+
+            #[test]
+            fn pages_are_offset() {
+                #model::__diesel_filter_test_pages();
+            }
+        }
+    }
+    .into()
+}
+
+/// Attribute form of the derive, its arguments are the struct options of
+/// `#[diesel_filter(...)]` and the filter annotations are removed from the model
+#[proc_macro_attribute]
+pub fn diesel_filter(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = proc_macro2::TokenStream::from(args);
+    let mut input = parse_macro_input!(item as DeriveInput);
+    if !args.is_empty() {
+        input.attrs.push(parse_quote! { #[diesel_filter(#args)] });
+    }
+
+    let expanded = expand(input.clone()).unwrap_or_else(|err| err.to_compile_error());
+    strip_helpers(&mut input);
+    quote! {
+        #input
+
+        #expanded
+    }
+    .into()
+}
+
+/// Removes the attributes only the derive knows about, `garde` rules are kept
+/// when the model is validated itself
+fn strip_helpers(input: &mut DeriveInput) {
+    let validated = input.attrs.iter().any(|attr| {
+        attr.path().is_ident("derive") && attr.to_token_stream().to_string().contains("Validate")
+    });
+    input.attrs.retain(|attr| {
+        !["diesel_filter", "pagination", "table_name"]
+            .iter()
+            .any(|helper| attr.path().is_ident(helper))
+    });
+    if let Data::Struct(data) = &mut input.data {
+        for field in data.fields.iter_mut() {
+            field.attrs.retain(|attr| {
+                !attr.path().is_ident("filter") && (validated || !attr.path().is_ident("garde"))
+            });
+        }
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let table_path = match diesel_option::<Path>(&input.attrs, "table_name")? {
+        Some(path) => path,
+        None => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "please provide #[diesel(table_name = ...)] attribute",
+            ))
+        }
+    };
+    let struct_opts = DieselFilterOpts::parse(&input.attrs)?;
+
+    // A bare table name refers to the module `diesel print-schema` writes, `crate::schema`
+    // unless `schema_module` says otherwise, or to the module of its Postgres schema in there.
+    // `crate::` is the root of the deriving crate, it can't be shadowed by its imports
+    let schema_module = match &struct_opts.schema_module {
+        Some(module) => quote! { #module },
+        None => quote! { crate::schema },
+    };
+    let table_name = table_path.segments.last().unwrap().ident.clone();
+    let table = match (table_path.get_ident(), &struct_opts.schema) {
+        (Some(name), Some(schema)) => quote! { #schema_module::#schema::#name },
+        (Some(name), None) => quote! { #schema_module::#name },
+        (None, _) => quote! { #table_path },
+    };
+    let table_label = match &struct_opts.schema {
+        Some(schema) => format!("{}.{}", schema, table_name),
+        None => table_name.to_string(),
+    };
+
+    let pagination_opts = PaginationOpts::parse(&input.attrs)?;
+    let pagination = pagination_opts.is_some();
+    // Filters built in code, without the defaults of the deserialization, load the same pages
+    let (page, per_page) = {
+        let opts = pagination_opts.as_ref();
+        let arg = |default: Option<&syn::LitInt>, field: Ident| match default {
+            Some(default) => quote! { Some(filters.#field.map(i64::from).unwrap_or(#default)) },
+            None => quote! { filters.#field.map(i64::from) },
+        };
+        let per_page = arg(
+            opts.and_then(|opts| opts.default_per_page.as_ref()),
+            Ident::new("per_page", Span::call_site()),
+        );
+        (
+            arg(
+                opts.and_then(|opts| opts.default_page.as_ref()),
+                Ident::new("page", Span::call_site()),
+            ),
+            // Pages are capped as well, `per_page` is a parameter of the request
+            match &struct_opts.max_rows {
+                Some(max_rows) => quote! {
+                    Some(#per_page.unwrap_or(::diesel_filter::DEFAULT_PER_PAGE).min(#max_rows))
+                },
+                None => per_page,
+            },
+        )
+    };
+    let primary_key = diesel_primary_key(&input.attrs)?;
+
+    let frontends = match &struct_opts.frontend {
+        Some(frontend) => Frontends::only(frontend)?,
+        None => Frontends::enabled(),
+    };
+    // Saved searches round-trip through serde whatever the framework
+    let serde = frontends.serde() || cfg!(feature = "saved_search");
+
+    let struct_name = input.ident;
+    let vis = input.vis;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    // Loaded rows own their data, they are `'static` whatever the lifetimes of the model
+    let loaded_generics = input
+        .generics
+        .params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Lifetime(_) => quote! { 'static },
+            GenericParam::Type(ty) => ty.ident.to_token_stream(),
+            GenericParam::Const(param) => param.ident.to_token_stream(),
+        })
+        .collect::<Vec<_>>();
+    let loaded = match loaded_generics.is_empty() {
+        true => quote! { Self },
+        false => quote! { #struct_name<#( #loaded_generics ),*> },
+    };
+    let mut filters = vec![];
+    let mut sortables: Vec<Sortable> = vec![];
+    // Diesel can't load rows into borrowed fields, `filtered` needs an owned model
+    let mut loadable = true;
+    let mut primary_key_fields = vec![];
+    // The money bounds take values of the amount field
+    let mut amount_ty = None;
+
+    if let Data::Struct(data) = input.data {
+        if let Fields::Named(fields) = data.fields {
+            for field in fields.named {
+                loadable &= !borrows(&field.ty);
+                match field.ident {
+                    Some(name) => {
+                        let field_type = field.ty;
+                        let column = match diesel_option::<ColumnName>(&field.attrs, "column_name")?
+                        {
+                            Some(ColumnName(column)) => column,
+                            None => name.clone(),
+                        };
+                        if let (Some((amount, _)), Type::Path(ty)) =
+                            (&struct_opts.money, &field_type)
+                        {
+                            if *amount == column {
+                                amount_ty = Some(Type::from(FilterableType::from(ty)));
+                            }
+                        }
+                        if primary_key.contains(&name) {
+                            primary_key_fields.push((
+                                name.clone(),
+                                column.clone(),
+                                field_type.clone(),
+                            ));
+                        }
+                        let garde = field
+                            .attrs
+                            .iter()
+                            .filter(|attr| attr.path().is_ident("garde"))
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        let docs = field
+                            .attrs
+                            .iter()
+                            .filter(|attr| attr.path().is_ident("doc"))
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        for attr in field
+                            .attrs
+                            .iter()
+                            .filter(|attr| attr.path().is_ident("sortable"))
+                        {
+                            let opts = SortOpts::parse(attr)?;
+                            if opts.distance_from.is_some() {
+                                return Err(syn::Error::new_spanned(
+                                    attr,
+                                    "`distance_from` names the columns of both coordinates, move it to the struct",
+                                ));
+                            }
+                            let keys = match &opts.join {
+                                None => vec![(
+                                    opts.rename.unwrap_or_else(|| {
+                                        name.to_string().trim_start_matches("r#").to_owned()
+                                    }),
+                                    SortBy::Column,
+                                )],
+                                Some(join) => {
+                                    let prefix = opts.rename.clone().unwrap_or_else(|| {
+                                        join.rsplit('.').next().unwrap_or(join).to_owned()
+                                    });
+                                    let on = opts.on.clone().unwrap_or_else(|| "id".to_owned());
+                                    opts.columns
+                                        .iter()
+                                        .map(|joined| {
+                                            (
+                                                format!("{}.{}", prefix, joined),
+                                                SortBy::Join(
+                                                    join.clone(),
+                                                    on.clone(),
+                                                    joined.clone(),
+                                                ),
+                                            )
+                                        })
+                                        .collect()
+                                }
+                            };
+                            for (key, by) in keys {
+                                if sortables.iter().any(|sortable| sortable.key == key) {
+                                    return Err(syn::Error::new_spanned(
+                                        attr,
+                                        format!("the rows are already sortable by `{}`, name this key with `rename = \"...\"`", key),
+                                    ));
+                                }
+                                sortables.push(Sortable {
+                                    key,
+                                    column: column.clone(),
+                                    field: name.clone(),
+                                    nullable: field_type
+                                        .to_token_stream()
+                                        .to_string()
+                                        .starts_with("Option <"),
+                                    by,
+                                    nulls: opts.nulls.clone(),
+                                });
+                            }
+                        }
+                        for attr in field.attrs.into_iter() {
+                            if !attr.path().is_ident("filter") {
+                                continue;
+                            }
+                            let mut opts = FilterOpts::parse(&attr)?;
+                            // The field is the column related rows reference, their
+                            // column is filtered as text
+                            let field_ty = match &opts.exists_in {
+                                Some(Subquery {
+                                    related: Related::Count,
+                                    ..
+                                }) => "i64".to_owned(),
+                                Some(Subquery { column: None, .. }) => "bool".to_owned(),
+                                Some(_) => "String".to_owned(),
+                                None => opts
+                                    .as_ty
+                                    .as_ref()
+                                    .unwrap_or(&field_type)
+                                    .to_token_stream()
+                                    .to_string()
+                                    .replace(' ', ""),
+                            };
+                            let field_ty = field_ty.trim_start_matches("Option<");
+                            let bytea = field_ty.starts_with("Vec<u8>");
+                            match (bytea, &opts.kind) {
+                                (true, _) if opts.facet => {
+                                    return Err(syn::Error::new_spanned(
+                                        &attr,
+                                        "bytea columns can't be facets",
+                                    ))
+                                }
+                                (true, FilterKind::Basic) => opts.kind = FilterKind::Bytea,
+                                (true, FilterKind::Prefix) => {}
+                                (true, _) => {
+                                    return Err(syn::Error::new_spanned(
+                                        &attr,
+                                        "bytea columns are filtered with the `basic` or `prefix` kinds",
+                                    ))
+                                }
+                                (false, FilterKind::Prefix) => {
+                                    return Err(syn::Error::new_spanned(
+                                        &attr,
+                                        "`prefix` filters bytea columns, the field must be a `Vec<u8>`",
+                                    ))
+                                }
+                                (false, _) => {}
+                            }
+                            let array = !bytea && field_ty.starts_with("Vec<");
+                            match (array, opts.kind.is_array()) {
+                                (true, false) => {
+                                    return Err(syn::Error::new_spanned(
+                                        &attr,
+                                        "array columns are filtered with `contains` or `overlaps`",
+                                    ))
+                                }
+                                (false, true) => {
+                                    return Err(syn::Error::new_spanned(
+                                        &attr,
+                                        "`contains` and `overlaps` filter array columns, the field must be a `Vec`",
+                                    ))
+                                }
+                                (true, true) if opts.facet => {
+                                    return Err(syn::Error::new_spanned(
+                                        &attr,
+                                        "array columns can't be facets",
+                                    ))
+                                }
+                                // The filter takes several values of the elements
+                                (true, true) => opts.multiple = true,
+                                (false, false) => {}
+                            }
+                            let with_time_zone = [
+                                "DateTime<",
+                                "chrono::DateTime<",
+                                "OffsetDateTime",
+                                "time::OffsetDateTime",
+                            ]
+                            .iter()
+                            .any(|ty| field_ty.starts_with(ty));
+                            let timestamp = with_time_zone
+                                || [
+                                    "NaiveDateTime",
+                                    "chrono::NaiveDateTime",
+                                    "PrimitiveDateTime",
+                                    "time::PrimitiveDateTime",
+                                ]
+                                .iter()
+                                .any(|ty| field_ty.starts_with(ty));
+                            let interval = [
+                                "Duration",
+                                "chrono::Duration",
+                                "TimeDelta",
+                                "chrono::TimeDelta",
+                                "PgInterval",
+                                "diesel::pg::data_types::PgInterval",
+                            ]
+                            .iter()
+                            .any(|ty| field_ty.trim_end_matches('>') == *ty);
+                            if interval {
+                                if opts.facet {
+                                    return Err(syn::Error::new_spanned(
+                                        &attr,
+                                        "interval columns can't be facets",
+                                    ));
+                                }
+                                if !matches!(
+                                    opts.kind,
+                                    FilterKind::Basic | FilterKind::Gte | FilterKind::Lte
+                                ) {
+                                    return Err(syn::Error::new_spanned(
+                                        &attr,
+                                        "interval columns are filtered with the `basic`, `gte` or `lte` kinds",
+                                    ));
+                                }
+                            }
+                            let integer =
+                                ["i16", "i32", "i64"].contains(&field_ty.trim_end_matches('>'));
+                            if matches!(opts.kind, FilterKind::HasFlags) && !integer {
+                                return Err(syn::Error::new_spanned(
+                                    &attr,
+                                    "`has_flags` filters integer bitmasks, the field must be an `i16`, `i32` or `i64`",
+                                ));
+                            }
+                            if opts.tz.is_some() {
+                                if !timestamp {
+                                    return Err(syn::Error::new_spanned(
+                                        &attr,
+                                        "`tz` compares the dates of timestamp columns, the field must be a `NaiveDateTime`, `DateTime`, `PrimitiveDateTime` or `OffsetDateTime`",
+                                    ));
+                                }
+                                if opts.facet || !matches!(opts.kind, FilterKind::Basic) {
+                                    return Err(syn::Error::new_spanned(
+                                        &attr,
+                                        "`tz` filters compare dates, they can't have a kind or be facets",
+                                    ));
+                                }
+                            }
+
+                            let ty = match opts.as_ty.as_ref().unwrap_or(&field_type) {
+                                _ if opts.exists_in.is_some() => Some(match field_ty {
+                                    "String" => FilterableType::String,
+                                    ty => FilterableType::Foreign(ty.to_owned()),
+                                }),
+                                // Borrowed strings are filtered with an owned `String`
+                                ty if borrows_str(ty) => Some(FilterableType::String),
+                                Type::Path(ty) => Some(FilterableType::from(ty)),
+                                _ => None,
+                            };
+                            if let Some(ty) = ty {
+                                if !opts.one_of.is_empty()
+                                    && (!matches!(ty, FilterableType::String)
+                                        || bytea
+                                        || array
+                                        || opts.tz.is_some())
+                                {
+                                    return Err(syn::Error::new_spanned(
+                                        &attr,
+                                        "`one_of` filters text columns, the field must be a `String`",
+                                    ));
+                                }
+                                if matches!(opts.kind, FilterKind::Fulltext)
+                                    && !matches!(ty, FilterableType::String)
+                                {
+                                    return Err(syn::Error::new_spanned(
+                                        &attr,
+                                        "`fulltext` filters text columns, the field must be a `String`",
+                                    ));
+                                }
+                                // The filter takes `YYYY-MM-DD` dates or intervals like
+                                // `1 hour`, cast by Postgres, and hstore keys or pairs
+                                let hstore = matches!(
+                                    opts.kind,
+                                    FilterKind::HstoreKey | FilterKind::HstoreKv
+                                );
+                                let ty = match opts.tz.is_some() || interval || hstore {
+                                    true => FilterableType::String,
+                                    false => ty,
+                                };
+                                let garde = garde.clone();
+
+                                filters.push(Filter {
+                                    name: opts.rename.clone().unwrap_or_else(|| name.clone()),
+                                    field: name.clone(),
+                                    column: column.clone(),
+                                    ty,
+                                    opts,
+                                    garde,
+                                    docs: docs.clone(),
+                                    nullable: field_type
+                                        .to_token_stream()
+                                        .to_string()
+                                        .starts_with("Option <"),
+                                    with_time_zone,
+                                    interval,
+                                });
+                                continue;
+                            }
+                            return Err(syn::Error::new_spanned(
+                                &field_type,
+                                "this type is not supported",
+                            ));
+                        }
+                    }
+                    None => continue,
+                }
+            }
+        }
+    }
+
+    let filter_struct_ident = Ident::new(&format!("{}Filters", struct_name), struct_name.span());
+    let filter_ident = Ident::new(&format!("{}Filter", struct_name), struct_name.span());
+
+    if filters.is_empty() {
+        return Err(syn::Error::new(
+            struct_name.span(),
+            "please annotate at least one field to filter with #[filter] on your struct",
+        ));
+    }
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("sortable"))
+    {
+        let opts = SortOpts::parse(attr)?;
+        let (lat, lng) = match opts.distance_from {
+            Some(columns) => columns,
+            None => return Err(syn::Error::new_spanned(
+                attr,
+                "`#[sortable]` on the struct expects `distance_from(lat = \"...\", lng = \"...\")`",
+            )),
+        };
+        let key = opts.rename.unwrap_or_else(|| "distance".to_owned());
+        if sortables.iter().any(|sortable| sortable.key == key) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                format!(
+                    "the rows are already sortable by `{}`, name this key with `rename = \"...\"`",
+                    key
+                ),
+            ));
+        }
+        sortables.push(Sortable {
+            key,
+            field: lat.clone(),
+            nullable: false,
+            column: lat,
+            by: SortBy::Distance(lng),
+            nulls: opts.nulls,
+        });
+    }
+    // The point the distances are measured from
+    let near = sortables
+        .iter()
+        .any(|sortable| matches!(sortable.by, SortBy::Distance(_)));
+    if near {
+        if let Some(filter) = filters
+            .iter()
+            .find(|f| f.name == "near_lat" || f.name == "near_lng")
+        {
+            return Err(syn::Error::new(
+                filter.name.span(),
+                "`near_lat` and `near_lng` are the point of the `distance_from` sort, name this filter with `rename = \"...\"`",
+            ));
+        }
+    }
+
+    let money = match (&struct_opts.money, amount_ty) {
+        (Some((amount, currency)), Some(ty)) => {
+            if let Some(filter) = filters
+                .iter()
+                .find(|f| ["min_amount", "max_amount", "currency"].contains(&f.name.to_string().as_str()))
+            {
+                return Err(syn::Error::new(
+                    filter.name.span(),
+                    "`min_amount`, `max_amount` and `currency` are the filters of `money`, name this one with `rename = \"...\"`",
+                ));
+            }
+            Some((amount.clone(), currency.clone(), ty))
+        }
+        (Some((amount, _)), None) => {
+            return Err(syn::Error::new(
+                amount.span(),
+                "the `money` amount is bounded by values of its field, the column must be a field of the model",
+            ))
+        }
+        (None, _) => None,
+    };
+
+    // Filters can share a time zone parameter
+    let mut tz_params: Vec<Ident> = vec![];
+    for filter in &filters {
+        if let Some(tz) = &filter.opts.tz {
+            if filters.iter().any(|f| f.name == *tz) {
+                return Err(syn::Error::new(
+                    tz.span(),
+                    format!("there is already a filter named `{}`", tz),
+                ));
+            }
+            if !tz_params.contains(tz) {
+                tz_params.push(tz.clone());
+            }
+        }
+    }
+    if struct_opts.static_dispatch {
+        if let Some(filter) = filters.iter().find(|f| f.opts.cfg.is_some()) {
+            return Err(syn::Error::new(
+                filter.name.span(),
+                "the statically typed query has every filter in its type, `cfg` filters need the dynamic dispatch, remove `static_dispatch`",
+            ));
+        }
+    }
+    if let Some(sortable) = sortables.first() {
+        if let Some(filter) = filters.iter().find(|f| f.name == "sort") {
+            return Err(syn::Error::new(
+                filter.name.span(),
+                "the `sort` parameter sorts the rows by the `#[sortable]` fields, name this filter with `rename = \"...\"`",
+            ));
+        }
+        if struct_opts.static_dispatch {
+            return Err(syn::Error::new(
+                sortable.column.span(),
+                "`#[sortable]` fields are sorted by with the dynamic dispatch, remove `static_dispatch`",
+            ));
+        }
+    }
+
+    // Two sources of the same parameter would be a duplicate field of the filters
+    // struct, or an alias serde can't tell from the other parameter
+    let mut params: Vec<(String, String, Option<Span>)> = vec![];
+    for filter in &filters {
+        let field = filter.field.to_string().trim_start_matches("r#").to_owned();
+        let source = match filter.field == filter.name {
+            true => format!("the `{}` field", field),
+            false => format!("the renamed `{}` field", field),
+        };
+        params.push((
+            filter.name.to_string().trim_start_matches("r#").to_owned(),
+            source,
+            Some(filter.name.span()),
+        ));
+        for alias in &filter.opts.aliases {
+            params.push((
+                alias.clone(),
+                format!("an alias of the `{}` field", field),
+                Some(filter.field.span()),
+            ));
+        }
+    }
+    // The parameters of the struct options, the errors point at the field
+    if !struct_opts.search.is_empty() {
+        params.push(("search".to_owned(), "`search`".to_owned(), None));
+    }
+    if pagination {
+        params.push(("page".to_owned(), "the pagination".to_owned(), None));
+        params.push(("per_page".to_owned(), "the pagination".to_owned(), None));
+    }
+    for (i, (param, source, span)) in params.iter().enumerate() {
+        if let Some((_, first, first_span)) =
+            params[..i].iter().find(|(other, _, _)| other == param)
+        {
+            return Err(syn::Error::new(
+                span.or(*first_span).unwrap_or_else(Span::call_site),
+                format!(
+                    "`{}` is the parameter of both {} and {}, name one of them with `rename = \"...\"`",
+                    param, first, source
+                ),
+            ));
+        }
+    }
+
+    let field_names = filters
+        .iter()
+        .map(|f| f.name.clone())
+        .chain(match struct_opts.search.is_empty() {
+            true => vec![],
+            false => vec![Ident::new("search", Span::call_site())],
+        })
+        .chain(match money {
+            Some(_) => vec![
+                Ident::new("min_amount", Span::call_site()),
+                Ident::new("max_amount", Span::call_site()),
+                Ident::new("currency", Span::call_site()),
+            ],
+            None => vec![],
+        })
+        .chain(tz_params.iter().cloned())
+        .chain(match sortables.is_empty() {
+            true => vec![],
+            false => vec![Ident::new("sort", Span::call_site())],
+        })
+        .chain(match near {
+            true => vec![
+                Ident::new("near_lat", Span::call_site()),
+                Ident::new("near_lng", Span::call_site()),
+            ],
+            false => vec![],
+        })
+        .chain(match pagination {
+            true => vec![
+                Ident::new("page", Span::call_site()),
+                Ident::new("per_page", Span::call_site()),
+            ],
+            false => vec![],
+        })
+        .collect::<Vec<_>>();
+    // The other fields are always there
+    let field_cfgs = filters
+        .iter()
+        .map(|f| f.opts.cfg_attr())
+        .chain(std::iter::repeat_with(|| quote! {}))
+        .take(field_names.len())
+        .collect::<Vec<_>>();
+    let cfg_of = |name: &Ident| {
+        field_names
+            .iter()
+            .position(|field| field == name)
+            .map(|i| field_cfgs[i].clone())
+            .unwrap_or_default()
+    };
+
+    let proto_impl = match &struct_opts.proto {
+        Some(proto) if cfg!(feature = "tonic") => {
+            let mut conversions = filters
+                .iter()
+                .map(|f| {
+                    let field = &f.name;
+                    let proto_field = match &f.opts.proto {
+                        Some(name) => Ident::new(name, Span::call_site()),
+                        None => field.clone(),
+                    };
+                    let cfg = f.opts.cfg_attr();
+                    quote! {
+                        #cfg
+                        #field: ::diesel_filter::IntoFilter::into_filter(request.#proto_field),
+                    }
+                })
+                .collect::<Vec<_>>();
+            if !struct_opts.search.is_empty() {
+                conversions.push(quote! { search: None, });
+            }
+            if money.is_some() {
+                conversions.push(quote! { min_amount: None, max_amount: None, currency: None, });
+            }
+            conversions.extend(tz_params.iter().map(|tz| quote! { #tz: None, }));
+            if !sortables.is_empty() {
+                conversions.push(quote! { sort: None, });
+            }
+            if near {
+                conversions.push(quote! { near_lat: None, near_lng: None, });
+            }
+            if pagination {
+                conversions.push(quote! {
+                    page: ::diesel_filter::IntoFilter::into_filter(request.page),
+                    per_page: ::diesel_filter::IntoFilter::into_filter(request.per_page),
+                });
+            }
+
+            quote! {
+                impl From<#proto> for #filter_struct_ident {
+                    fn from(request: #proto) -> Self {
+                        Self {
+                            #( #conversions )*
+                        }
+                    }
+                }
+            }
+        }
+        _ => quote! {},
+    };
+
+    let rsql_impl = match cfg!(feature = "rsql") {
+        true => {
+            let arms = filters
+                .iter()
+                .map(|f| {
+                    let field = &f.name;
+                    let selector = field.to_string().trim_start_matches("r#").to_owned();
+                    let constraint = match f.opts.kind {
+                        FilterKind::Substr | FilterKind::SubstrInsensitive => {
+                            quote! { constraint.trim_wildcards() }
+                        }
+                        FilterKind::Basic
+                        | FilterKind::Insensitive
+                        | FilterKind::Lower
+                        | FilterKind::Contains
+                        | FilterKind::Overlaps
+                        | FilterKind::Bytea
+                        | FilterKind::Prefix
+                        | FilterKind::Gte
+                        | FilterKind::Lte
+                        | FilterKind::Fulltext
+                        | FilterKind::HasFlags
+                        | FilterKind::HstoreKey
+                        | FilterKind::HstoreKv => quote! { constraint },
+                    };
+                    let value = match (&f.opts.kind, f.opts.multiple) {
+                        (FilterKind::Gte, _) => quote! { compared(">=") },
+                        (FilterKind::Lte, _) => quote! { compared("<=") },
+                        (_, true) => quote! { values() },
+                        (_, false) => quote! { value() },
+                    };
+                    let cfg = f.opts.cfg_attr();
+                    quote! {
+                        #cfg
+                        #selector => filters.#field = Some(#constraint.#value?),
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            quote! {
+                impl #filter_struct_ident {
+                    /// Parses an RSQL expression (`name==foo;ids=in=(1,2)`) into the filters
+                    pub fn from_rsql(input: &str) -> Result<Self, ::diesel_filter::rsql::RsqlError> {
+                        let mut filters = Self {
+                            #( #field_cfgs #field_names: None, )*
+                        };
+                        for constraint in ::diesel_filter::rsql::parse(input)? {
+                            match constraint.selector.as_str() {
+                                #( #arms )*
+                                _ => {
+                                    return Err(::diesel_filter::rsql::RsqlError::UnknownSelector(
+                                        constraint.selector,
+                                    ))
+                                }
+                            }
+                        }
+                        Ok(filters)
+                    }
+                }
+            }
+        }
+        false => quote! {},
+    };
+
+    let odata_impl = match cfg!(feature = "odata") {
+        true => {
+            let arms = filters
+                .iter()
+                .map(|f| {
+                    let field = &f.name;
+                    let property = field.to_string().trim_start_matches("r#").to_owned();
+                    let operator = match f.opts.kind {
+                        FilterKind::Substr | FilterKind::SubstrInsensitive => {
+                            quote! { ::diesel_filter::odata::Operator::Contains }
+                        }
+                        FilterKind::Prefix => {
+                            quote! { ::diesel_filter::odata::Operator::StartsWith }
+                        }
+                        FilterKind::Gte => quote! { ::diesel_filter::odata::Operator::Ge },
+                        FilterKind::Lte => quote! { ::diesel_filter::odata::Operator::Le },
+                        FilterKind::HasFlags => quote! { ::diesel_filter::odata::Operator::Has },
+                        FilterKind::Basic
+                        | FilterKind::Insensitive
+                        | FilterKind::Lower
+                        | FilterKind::Contains
+                        | FilterKind::Overlaps
+                        | FilterKind::Bytea
+                        | FilterKind::Fulltext
+                        | FilterKind::HstoreKey
+                        | FilterKind::HstoreKv => {
+                            quote! { ::diesel_filter::odata::Operator::Eq }
+                        }
+                    };
+                    let value = match f.opts.multiple {
+                        true => quote! { values },
+                        false => quote! { value },
+                    };
+                    let cfg = f.opts.cfg_attr();
+                    quote! {
+                        #cfg
+                        #property => filters.#field = Some(comparison.#value(#operator)?),
+                    }
+                })
+                .collect::<Vec<_>>();
+            let paginate = match pagination {
+                true => quote! {
+                    let (page, per_page) = query.pagination()?;
+                    filters.page = page
+                        .map(::std::convert::TryInto::try_into)
+                        .transpose()
+                        .map_err(|_| ::diesel_filter::odata::ODataError::Unsupported("$skip out of range"))?;
+                    filters.per_page = per_page
+                        .map(::std::convert::TryInto::try_into)
+                        .transpose()
+                        .map_err(|_| ::diesel_filter::odata::ODataError::Unsupported("$top out of range"))?;
+                },
+                false => quote! {
+                    if query.top.is_some() || query.skip.is_some() {
+                        return Err(::diesel_filter::odata::ODataError::Unsupported("$top and $skip"));
+                    }
+                },
+            };
+            let (orderby, sort) = match sortables.is_empty() {
+                true => (
+                    quote! {
+                        if query.orderby.is_some() {
+                            return Err(::diesel_filter::odata::ODataError::Unsupported("$orderby"));
+                        }
+                    },
+                    quote! {},
+                ),
+                false => (quote! {}, quote! { filters.sort = query.sort(); }),
+            };
+
+            quote! {
+                impl #filter_struct_ident {
+                    /// Maps the OData query options (`$filter`, `$top`, `$skip`) onto the filters
+                    pub fn from_odata(
+                        query: &::diesel_filter::odata::ODataQuery,
+                    ) -> Result<Self, ::diesel_filter::odata::ODataError> {
+                        #orderby
+                        let mut filters = Self {
+                            #( #field_cfgs #field_names: None, )*
+                        };
+                        #sort
+                        for comparison in query.comparisons()? {
+                            match comparison.property.as_str() {
+                                #( #arms )*
+                                _ => {
+                                    return Err(::diesel_filter::odata::ODataError::UnknownProperty(
+                                        comparison.property,
+                                    ))
+                                }
+                            }
+                        }
+                        #paginate
+                        Ok(filters)
+                    }
+                }
+            }
+        }
+        false => quote! {},
+    };
+
+    let json_impl = match cfg!(feature = "json") {
+        true => {
+            let mut arms = filters
+                .iter()
+                .map(|f| {
+                    let field = &f.name;
+                    let name = field.to_string().trim_start_matches("r#").to_owned();
+                    let operator = match f.opts.kind {
+                        FilterKind::Basic | FilterKind::Lower | FilterKind::Bytea => "eq",
+                        FilterKind::Substr => "like",
+                        FilterKind::Insensitive | FilterKind::SubstrInsensitive => "ilike",
+                        FilterKind::Contains => "contains",
+                        FilterKind::Overlaps => "overlaps",
+                        FilterKind::Prefix => "prefix",
+                        FilterKind::Gte => "gte",
+                        FilterKind::Lte => "lte",
+                        FilterKind::Fulltext => "match",
+                        FilterKind::HasFlags => "has_flags",
+                        FilterKind::HstoreKey => "has_key",
+                        FilterKind::HstoreKv => "eq",
+                    };
+                    // Custom ops are named after their macro
+                    let op = f.opts.op.as_ref().and_then(|op| op.segments.last());
+                    let operator =
+                        op.map_or_else(|| operator.to_owned(), |op| op.ident.to_string());
+                    let value = match f.opts.multiple {
+                        true => quote! { values },
+                        false => quote! { value },
+                    };
+                    let cfg = f.opts.cfg_attr();
+                    quote! {
+                        #cfg
+                        #name => filters.#field = Some(condition.#value(#operator)?),
+                    }
+                })
+                .collect::<Vec<_>>();
+            if !sortables.is_empty() {
+                arms.push(quote! {
+                    "sort" => filters.sort = Some(condition.value("eq")?),
+                });
+            }
+            if near {
+                arms.push(quote! {
+                    "near_lat" => filters.near_lat = Some(condition.value("eq")?),
+                    "near_lng" => filters.near_lng = Some(condition.value("eq")?),
+                });
+            }
+            if money.is_some() {
+                arms.push(quote! {
+                    "min_amount" => filters.min_amount = Some(condition.value("gte")?),
+                    "max_amount" => filters.max_amount = Some(condition.value("lte")?),
+                    "currency" => filters.currency = Some(condition.value("eq")?),
+                });
+            }
+            if pagination {
+                arms.push(quote! {
+                    "page" => filters.page = Some(condition.value("eq")?),
+                    "per_page" => filters.per_page = Some(condition.value("eq")?),
+                });
+            }
+
+            quote! {
+                impl #filter_struct_ident {
+                    /// Reads the filters from a JSON payload (`{"name": {"ilike": "foo"}}`)
+                    pub fn from_json(
+                        value: ::diesel_filter::json::Value,
+                    ) -> Result<Self, ::diesel_filter::json::JsonFilterError> {
+                        let mut filters = Self {
+                            #( #field_cfgs #field_names: None, )*
+                        };
+                        for condition in ::diesel_filter::json::conditions(value)? {
+                            match condition.field.as_str() {
+                                #( #arms )*
+                                _ => {
+                                    return Err(::diesel_filter::json::JsonFilterError::UnknownField(
+                                        condition.field,
+                                    ))
+                                }
+                            }
+                        }
+                        Ok(filters)
+                    }
+                }
+            }
+        }
+        false => quote! {},
+    };
+
+    // The constraints only, the sort and the page stay the ones of the request
+    let defaults_impl = match struct_opts.default_filters {
+        true => {
+            // The sort, near and page fields come last in `field_names`
+            let constraints = field_names.len()
+                - usize::from(!sortables.is_empty())
+                - 2 * usize::from(near)
+                - 2 * usize::from(pagination);
+            let (merged, kept) = field_names.split_at(constraints);
+            let merged_cfgs = &field_cfgs[..constraints];
+            quote! {
+                impl #filter_struct_ident {
+                    /// The filters with the ones they leave unset taken from `defaults`,
+                    /// e.g constraints of the deployment under the filters of a request.
+                    /// The sort and the page are the ones of the filters
+                    pub fn with_defaults(self, defaults: &Self) -> Self {
+                        Self {
+                            #( #merged_cfgs #merged: self.#merged.or_else(|| defaults.#merged.clone()), )*
+                            #( #kept: self.#kept, )*
+                        }
+                    }
+
+                    /// `with_defaults` with the `DefaultFilters` of the deployment, if it has any
+                    pub fn with_default_filters(self) -> Self {
+                        match <Self as ::diesel_filter::DefaultFilters>::default_filters() {
+                            Some(defaults) => self.with_defaults(&defaults),
+                            None => self,
+                        }
+                    }
+                }
+            }
+        }
+        false => quote! {},
+    };
+
+    let saved_search_impl = match cfg!(feature = "saved_search") {
+        true => quote! {
+            impl #filter_struct_ident {
+                /// Reads filters saved with `to_json_string`
+                pub fn from_json_str(json: &str) -> Result<Self, ::diesel_filter::saved_search::Error> {
+                    ::diesel_filter::saved_search::from_json_str(json)
+                }
+
+                /// Serializes the filters, leaving out the unset ones, e.g to
+                /// save a search in a JSONB column and replay it later
+                pub fn to_json_string(&self) -> Result<String, ::diesel_filter::saved_search::Error> {
+                    ::diesel_filter::saved_search::to_json_string(self)
+                }
+            }
+        },
+        false => quote! {},
+    };
+
+    let search_impl = match cfg!(feature = "search_syntax") {
+        true => {
+            let arms = filters
+                .iter()
+                .map(|f| {
+                    let field = &f.name;
+                    let qualifier = field.to_string().trim_start_matches("r#").to_owned();
+                    let cfg = f.opts.cfg_attr();
+                    match f.opts.multiple {
+                        true => quote! {
+                            #cfg
+                            #qualifier => filters
+                                .#field
+                                .get_or_insert_with(Vec::new)
+                                .extend(::diesel_filter::search::values(&qualifier, &value)?),
+                        },
+                        false => quote! {
+                            #cfg
+                            #qualifier => filters.#field = Some(::diesel_filter::search::value(&qualifier, &value)?),
+                        },
+                    }
+                })
+                .collect::<Vec<_>>();
+            let words = match struct_opts.search.is_empty() {
+                true => quote! {
+                    return Err(::diesel_filter::search::SearchSyntaxError::UnexpectedText(
+                        terms.words.join(" "),
+                    ));
+                },
+                false => quote! { filters.search = Some(terms.words.join(" ")); },
+            };
+
+            quote! {
+                impl #filter_struct_ident {
+                    /// Parses a search like `status:open author:"jane doe" urgent` into the filters
+                    pub fn from_search(input: &str) -> Result<Self, ::diesel_filter::search::SearchSyntaxError> {
+                        let terms = ::diesel_filter::search::parse(input)?;
+                        let mut filters = Self {
+                            #( #field_cfgs #field_names: None, )*
+                        };
+                        for (qualifier, value) in terms.qualifiers {
+                            match qualifier.as_str() {
+                                #( #arms )*
+                                _ => {
+                                    return Err(::diesel_filter::search::SearchSyntaxError::UnknownQualifier(
+                                        qualifier,
+                                    ))
+                                }
+                            }
+                        }
+                        if !terms.words.is_empty() {
+                            #words
+                        }
+                        Ok(filters)
+                    }
+                }
+            }
+        }
+        false => quote! {},
+    };
+
+    // Unset filters are left out when the filters are serialized
+    let skip_none = match serde {
+        true => quote! { #[serde(skip_serializing_if = "Option::is_none")] },
+        false => quote! {},
+    };
+
+    let mut fields = vec![];
+    let mut queries = vec![];
+    // The conditions of `filter_sqlite` and `filter_mysql`, the ones those databases can compare
+    let mut sqlite_queries = vec![];
+    let mut mysql_queries = vec![];
+    // The generated methods don't depend on the imports of the user, the traits are
+    // imported in a block of their own and in the bodies building the queries
+    let trait_imports = quote! {
+        use ::diesel::expression_methods::*;
+        use ::diesel::{Connection as _, OptionalExtension as _, QueryDsl as _, RunQueryDsl as _};
+    };
+    let trait_imports = match pagination {
+        true => quote! { #trait_imports use ::diesel_filter::Paginate as _; },
+        false => trait_imports,
+    };
+    let mut uses = vec![trait_imports.clone()];
+    let mut helpers = vec![];
+    let mut variants = vec![];
+    let mut predicates = vec![];
+    // Typos between the model and `table!` fail at the field rather than in the generated code
+    let mut column_checks = struct_opts
+        .search
+        .iter()
+        .map(|column| quote_spanned! {column.span()=> let _ = #table::#column; })
+        .collect::<Vec<_>>();
+    let mut static_queries = vec![];
+    let mut static_prelude = vec![];
+    let mut raw_conditions = vec![];
+    // Qualified like diesel does, for the predicates appended to raw SQL
+    let raw_column = |column: &Ident| {
+        let column = column.to_string().trim_start_matches("r#").to_owned();
+        match &struct_opts.schema {
+            Some(schema) => format!("\"{}\".\"{}\".\"{}\"", schema, table_name, column),
+            None => format!("\"{}\".\"{}\"", table_name, column),
+        }
+    };
+    // MySQL quotes the identifiers with backticks, its double quotes are strings
+    let mysql_column = |column: &Ident| {
+        let column = column.to_string().trim_start_matches("r#").to_owned();
+        match &struct_opts.schema {
+            Some(schema) => format!("`{}`.`{}`.`{}`", schema, table_name, column),
+            None => format!("`{}`.`{}`", table_name, column),
+        }
+    };
+    let mut facets = vec![];
+    // Soft deleted rows are left out of every query, `filter` and the counts agree
+    if let Some(column) = &struct_opts.soft_delete {
+        column_checks.push(quote_spanned! {column.span()=> let _ = #table::#column; });
+        let soft_delete = quote! {
+            query = query.filter(#table::#column.is_null());
+        };
+        queries.push(soft_delete.clone());
+        sqlite_queries.push(soft_delete.clone());
+        mysql_queries.push(soft_delete);
+        static_queries.push(quote! { #table::#column.is_null() });
+        let condition = format!("{} IS NULL", raw_column(column));
+        raw_conditions.push(quote! {
+            conditions.push(#condition.to_owned());
+        });
+    }
+    let mut has_multiple = false;
+    let mut has_lower = false;
+    let mut one_of_enums = vec![];
+    // The filters whose values past `max_values` are rejected
+    let mut limited_values = vec![];
+    // The filter whose values `filtered_chunked` splits, with its chunk size
+    let mut chunked = None;
+    // Every filter set alone for `diesel_filter_tests!`, with a fragment of its SQL
+    let mut test_cases = vec![];
+    // The fragment `filtered_raw` appends for every filter set alone
+    let mut sql_templates = vec![];
+    // `fts_table` rows are joined on a single column primary key
+    let fts_key = match primary_key.as_slice() {
+        [key] => primary_key_fields
+            .iter()
+            .find(|(name, _, _)| name == key)
+            .map(|(_, column, _)| column.clone()),
+        _ => None,
+    };
+    for filter in filters {
+        let field = filter.name;
+        let column = filter.column;
+        let column_sql = raw_column(&column);
+        let filter_column = quote! { #table::#column };
+        let fulltext_sql = match matches!(filter.opts.kind, FilterKind::Fulltext) {
+            true => fulltext_sql(
+                &column,
+                &column_sql,
+                filter.opts.fts_table.as_deref(),
+                fts_key.as_ref().map(|key| (key, raw_column(key))),
+            )?,
+            false => Default::default(),
+        };
+        column_checks.push(quote_spanned! {column.span()=> let _ = #table::#column; });
+        let opts = filter.opts;
+        let cfg = opts.cfg_attr();
+        if let Some(size) = opts.chunk_size {
+            if chunked.is_some() {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "`filtered_chunked` splits the values of a single filter, keep `chunk_size` on one of them",
+                ));
+            }
+            chunked = Some((field.clone(), size, cfg.clone()));
+        }
+        // Past `max_values` the values are dropped, or rejected: the queries match no
+        // rows, without the values, and `filtered` returns a `TooManyValues`
+        let (truncate, raw_truncate, predicate_truncate, values) = match opts.max_values {
+            Some(max) if opts.truncate_values => {
+                let truncate = quote! { let filter = &filter[..filter.len().min(#max)]; };
+                (
+                    truncate.clone(),
+                    truncate.clone(),
+                    truncate,
+                    quote! { filters.#field.as_deref().map(|values| &values[..values.len().min(#max)]).unwrap_or(&[]) },
+                )
+            }
+            // `diesel_filter::Error` comes with the validation and pagination features
+            Some(_)
+                if !cfg!(any(
+                    feature = "validation",
+                    feature = "garde",
+                    feature = "pagination"
+                )) =>
+            {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "`max_values` rejects the values with a `diesel_filter::Error`, enable the `pagination` or `validation` feature, or drop them with `overflow = \"truncate\"`",
+                ))
+            }
+            Some(max) => {
+                limited_values.push((field.clone(), max));
+                let rejected = quote! { ::diesel::IntoSql::into_sql::<::diesel::sql_types::Bool>(false) };
+                (
+                    quote! {
+                        let filter = match filter.len() > #max {
+                            true => {
+                                query = query.filter(#rejected);
+                                &filter[..0]
+                            }
+                            false => &filter[..],
+                        };
+                    },
+                    quote! {
+                        let filter = match filter.len() > #max {
+                            true => {
+                                conditions.push("FALSE".to_owned());
+                                &filter[..0]
+                            }
+                            false => &filter[..],
+                        };
+                    },
+                    quote! {
+                        if filter.len() > #max {
+                            return Box::new(::diesel::NullableExpressionMethods::nullable(#rejected));
+                        }
+                    },
+                    quote! { filters.#field.as_deref().filter(|values| values.len() <= #max).unwrap_or(&[]) },
+                )
+            }
+            None => (
+                quote! {},
+                quote! {},
+                quote! {},
+                quote! { filters.#field.as_deref().unwrap_or(&[]) },
+            ),
+        };
+        let ty: Type = match opts.one_of.is_empty() {
+            true => filter.ty.into(),
+            false => {
+                let ident = Ident::new(
+                    &format!("{}{}", struct_name, camel_case(&field.to_string())),
+                    field.span(),
+                );
+                one_of_enums.push(one_of_enum(&ident, &opts.one_of, frontends, serde)?);
+                parse_quote! { #ident }
+            }
+        };
+        // The types a value can be made up for, other filters are left out of the tests
+        let sample = match ty.to_token_stream().to_string().replace(' ', "").as_str() {
+            _ if !opts.one_of.is_empty() => {
+                let variant = variant_name(&opts.one_of[0])?;
+                Some(quote! { #ty::#variant })
+            }
+            "String" => {
+                let sample = match opts.kind {
+                    _ if opts.tz.is_some() => "2024-01-02",
+                    _ if filter.interval => "1 hour",
+                    FilterKind::Bytea | FilterKind::Prefix => "00",
+                    FilterKind::HstoreKv => "key:value",
+                    _ => "sample",
+                };
+                Some(quote! { #sample.to_owned() })
+            }
+            "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64" => Some(quote! { 1 }),
+            "f32" | "f64" => Some(quote! { 1.0 }),
+            "bool" => Some(quote! { true }),
+            "::uuid::Uuid" => Some(quote! { ::uuid::Uuid::nil() }),
+            _ => None,
+        };
+        if let (Some(sample), None) = (sample, &opts.op) {
+            let name = field.to_string().trim_start_matches("r#").to_owned();
+            let sample = match opts.multiple {
+                true => quote! { vec![#sample] },
+                false => sample,
+            };
+            // Side tables are searched by the key rather than the column
+            let fragment = opts.fts_table.clone().unwrap_or_else(|| column_sql.clone());
+            test_cases.push(quote! {
+                #cfg
+                (#name, { let mut filters = unset(); filters.#field = Some(#sample); filters }, #fragment)
+            });
+        }
+        if let (FilterKind::Lower, None) = (&opts.kind, &opts.exists_in) {
+            has_lower = true;
+        }
+        if opts.facet {
+            let value_ty = match filter.nullable {
+                true => quote! { Option<#ty> },
+                false => quote! { #ty },
+            };
+            facets.push((field.clone(), filter_column.clone(), value_ty));
+        }
+        let docs = filter.docs;
+
+        let mut validate = match &opts.validate {
+            Some(rules) if cfg!(feature = "validation") => quote! { #[validate(#rules)] },
+            _ => quote! {},
+        };
+        if cfg!(feature = "garde") {
+            // garde requires every field to be annotated
+            let garde = match filter.garde.is_empty() {
+                true => quote! { #[garde(skip)] },
+                false => {
+                    let garde = filter.garde;
+                    quote! { #( #garde )* }
+                }
+            };
+            validate.extend(garde);
+        }
+
+        let mut aliases = vec![];
+        if !opts.aliases.is_empty() {
+            if frontends.rocket {
+                // Rocket only accepts the listed names once one is given
+                let name = field.to_string().trim_start_matches("r#").to_owned();
+                aliases.push(quote! { #[field(name = #name)] });
+                aliases.extend(
+                    opts.aliases
+                        .iter()
+                        .map(|alias| quote! { #[field(name = #alias)] }),
+                );
+            }
+            if serde {
+                aliases.extend(
+                    opts.aliases
+                        .iter()
+                        .map(|alias| quote! { #[serde(alias = #alias)] }),
+                );
+            }
+        }
+
+        // Still filtering, the field only warns its Rust users and is flagged
+        // deprecated in the schemas: schemars and salvo read `#[deprecated]`
+        if let Some(note) = &opts.deprecated {
+            aliases.push(quote! { #[deprecated(note = #note)] });
+            if frontends.poem {
+                aliases.push(quote! { #[oai(deprecated)] });
+            }
+        }
+
+        // The values of `multiple` filters are bound as a single array, `= ANY($1)`,
+        // rather than an `IN` list of a parameter per value: the statement is the same
+        // whatever the number of values, and so is its cached plan
+        let q = if opts.multiple {
+            has_multiple = true;
+            let patterns = substring_patterns(quote! { filter });
+            let mut attrs: Vec<proc_macro2::TokenStream> = match opts.delimiter {
+                Some(delimiter) if serde => {
+                    let helper = Ident::new(&format!("deserialize_{}", field), field.span());
+                    let path = format!("{}::{}", filter_struct_ident, helper);
+                    helpers.push(quote! {
+                        #cfg
+                        #[doc(hidden)]
+                        fn #helper<'de, D>(deserializer: D) -> Result<Option<Vec<#ty>>, D::Error>
+                        where
+                            D: ::serde::Deserializer<'de>,
+                        {
+                            ::diesel_filter::de::delimited(deserializer, #delimiter)
+                        }
+                    });
+                    vec![quote! { #[serde(default, deserialize_with = #path)] }]
+                }
+                _ => {
+                    let mut attrs = vec![];
+                    if frontends.rocket {
+                        attrs.push(quote! { #[field(default = Option::None)] });
+                    }
+                    if frontends.actix {
+                        attrs.push(quote! { #[serde(default, deserialize_with = "::diesel_filter::de::one_or_many")] });
+                    }
+                    attrs
+                }
+            };
+            if serde {
+                // PHP/Rails style `?ids[]=1&ids[]=2`
+                let alias = format!("{}[]", field.to_string().trim_start_matches("r#"));
+                attrs.push(quote! { #[serde(alias = #alias)] });
+                for alias in &opts.aliases {
+                    let alias = format!("{}[]", alias);
+                    attrs.push(quote! { #[serde(alias = #alias)] });
+                }
+            }
+            fields.push(quote! {
+                #cfg
+                #( #docs )*
+                #( #aliases )*
+                #( #attrs )*
+                #validate
+                #skip_none
+                pub #field: Option<Vec<#ty>>,
+            });
+            let values = match filter.interval {
+                true => interval_values(quote! { filter }, &filter_column),
+                false => quote! { filter },
+            };
+            match opts.kind {
+                FilterKind::Basic => {
+                    quote! { #table::#column.eq(any(#values)) }
+                }
+                FilterKind::Substr => {
+                    quote! { #table::#column.like(any(#patterns)) }
+                }
+                FilterKind::Insensitive => {
+                    quote! { #table::#column.ilike(any(filter)) }
+                }
+                FilterKind::SubstrInsensitive => {
+                    quote! { #table::#column.ilike(any(#patterns)) }
+                }
+                FilterKind::Lower => {
+                    let values = lower_values(quote! { filter });
+                    quote! { lower(#table::#column).eq(any(#values)) }
+                }
+                FilterKind::Contains => quote! { #table::#column.contains(filter) },
+                FilterKind::Overlaps => quote! { #table::#column.overlaps_with(filter) },
+                FilterKind::Bytea => {
+                    let values = hex_values(quote! { filter }, &filter_column);
+                    quote! { #table::#column.eq(any(#values)) }
+                }
+                FilterKind::Prefix => hex_prefix(quote! { filter }, &column_sql, true),
+                FilterKind::HstoreKey => hstore_key(quote! { filter }, &column_sql, true),
+                FilterKind::Gte
+                | FilterKind::Lte
+                | FilterKind::Fulltext
+                | FilterKind::HasFlags
+                | FilterKind::HstoreKv => {
+                    unreachable!(
+                        "comparisons, full-text queries, bitmasks and pairs take a single value"
+                    )
+                }
+            }
+        } else {
+            fields.push(quote! {
+                #cfg
+                #( #docs )*
+                #( #aliases )*
+                #validate
+                #skip_none
+                pub #field: Option<#ty>,
+            });
+            let value = match filter.interval {
+                true => interval_value(quote! { filter }, &filter_column),
+                false => quote! { filter },
+            };
+            match opts.kind {
+                FilterKind::Basic => {
+                    quote! { #table::#column.eq(#value) }
+                }
+                FilterKind::Substr => {
+                    let pattern = substring_pattern(quote! { filter });
+                    quote! { #table::#column.like(#pattern) }
+                }
+                FilterKind::Insensitive => {
+                    quote! { #table::#column.ilike(filter) }
+                }
+                FilterKind::SubstrInsensitive => {
+                    let pattern = substring_pattern(quote! { filter });
+                    quote! { #table::#column.ilike(#pattern) }
+                }
+                FilterKind::Lower => {
+                    quote! { lower(#table::#column).eq(lower(filter)) }
+                }
+                FilterKind::Contains | FilterKind::Overlaps => {
+                    unreachable!("array filters take multiple values")
+                }
+                FilterKind::Bytea => {
+                    let value = hex_value(quote! { filter }, &filter_column);
+                    quote! { #table::#column.eq(#value) }
+                }
+                FilterKind::Prefix => hex_prefix(quote! { filter }, &column_sql, false),
+                FilterKind::Gte => quote! { #table::#column.ge(#value) },
+                FilterKind::Lte => quote! { #table::#column.le(#value) },
+                FilterKind::Fulltext => fulltext(quote! { filter }, &fulltext_sql),
+                FilterKind::HasFlags => has_flags(quote! { filter }, &filter_column, &column_sql),
+                FilterKind::HstoreKey => hstore_key(quote! { filter }, &column_sql, false),
+                FilterKind::HstoreKv => hstore_kv(quote! { Some(filter.as_str()) }, &column_sql),
+            }
+        };
+
+        let q = match &opts.op {
+            Some(op) if struct_opts.static_dispatch => return Err(syn::Error::new_spanned(
+                op,
+                "`op` filters are only applied with the dynamic dispatch, remove `static_dispatch`",
+            )),
+            Some(op) => quote! { #op!(#filter_column, filter) },
+            None => q,
+        };
+
+        // SQLite and MySQL have no arrays, `ILIKE` or Postgres functions: the values of
+        // `multiple` filters are an `IN` list and `LIKE` ignores the case already
+        let postgres_only = opts.tz.is_some()
+            || opts.exists_in.is_some()
+            || filter.interval
+            || !opts.one_of.is_empty();
+        let portable_q = match (opts.multiple, &opts.kind) {
+            _ if postgres_only => None,
+            _ if opts.op.is_some() => Some(q.clone()),
+            (true, FilterKind::Basic) => Some(quote! { #filter_column.eq_any(filter) }),
+            (false, FilterKind::Insensitive) => Some(quote! { #filter_column.like(filter) }),
+            (false, FilterKind::Basic | FilterKind::Lower | FilterKind::Gte | FilterKind::Lte) => {
+                Some(q.clone())
+            }
+            _ => None,
+        };
+        let sqlite_q = match (opts.multiple, &opts.kind) {
+            _ if postgres_only => None,
+            _ if portable_q.is_some() => portable_q.clone(),
+            (false, FilterKind::Substr | FilterKind::SubstrInsensitive) => {
+                let pattern = substring_pattern(quote! { filter });
+                Some(quote! { #filter_column.like(#pattern) })
+            }
+            // The `fts_table` is a FTS5 table, its rowids are the ones of the model
+            (false, FilterKind::Fulltext) if opts.fts_table.is_some() => {
+                let fts_table = opts.fts_table.as_deref().unwrap_or_default();
+                let rowid = match &struct_opts.schema {
+                    Some(schema) => format!("\"{}\".\"{}\".rowid", schema, table_name),
+                    None => format!("\"{}\".rowid", table_name),
+                };
+                let matches = (
+                    format!(
+                        "{} IN (SELECT rowid FROM {} WHERE {} MATCH ",
+                        rowid, fts_table, fts_table
+                    ),
+                    ")".to_owned(),
+                );
+                Some(fulltext(quote! { filter }, &matches))
+            }
+            _ => None,
+        };
+        // The `||` of MySQL is a logical or, the patterns are built with `CONCAT`, and
+        // `fulltext` filters match the `FULLTEXT` index of the column in boolean mode
+        let mysql_q = match (opts.multiple, &opts.kind) {
+            _ if postgres_only => None,
+            _ if portable_q.is_some() => portable_q,
+            (false, FilterKind::Substr | FilterKind::SubstrInsensitive) => {
+                let pattern = concat_pattern(quote! { filter });
+                Some(quote! { #filter_column.like(#pattern) })
+            }
+            (false, FilterKind::Fulltext) if opts.fts_table.is_none() => {
+                let against = (
+                    format!("MATCH ({}) AGAINST (", mysql_column(&column)),
+                    " IN BOOLEAN MODE)".to_owned(),
+                );
+                Some(fulltext(quote! { filter }, &against))
+            }
+            _ => None,
+        };
+        match sqlite_q {
+            Some(q) => sqlite_queries.push(quote! {
+                #cfg
+                if let Some(ref filter) = filters.#field {
+                    #truncate
+                    query = query.filter(#q);
+                }
+            }),
+            None if struct_opts.sqlite => {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "SQLite compares with `=`, `IN`, `LIKE`, `>=`, `<=`, `lower`, the `MATCH` of a `fts_table` or an `op`, this filter has no counterpart there, remove `sqlite` from `backends`",
+                ))
+            }
+            None => {}
+        }
+        match mysql_q {
+            Some(q) => mysql_queries.push(quote! {
+                #cfg
+                if let Some(ref filter) = filters.#field {
+                    #truncate
+                    query = query.filter(#q);
+                }
+            }),
+            None if struct_opts.mysql => {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "MySQL compares with `=`, `IN`, `LIKE`, `>=`, `<=`, `lower`, `MATCH ... AGAINST` or an `op`, this filter has no counterpart there, remove `mysql` from `backends`",
+                ))
+            }
+            None => {}
+        }
+
+        // The filters struct holds the time zone, the filter enum compares UTC dates
+        let (q, predicate) = match &opts.tz {
+            Some(tz) => (
+                local_date(
+                    quote! { filter },
+                    quote! { filters.#tz.as_deref() },
+                    &column_sql,
+                    filter.with_time_zone,
+                    opts.multiple,
+                ),
+                local_date(
+                    quote! { filter },
+                    quote! { None::<&str> },
+                    &column_sql,
+                    filter.with_time_zone,
+                    opts.multiple,
+                ),
+            ),
+            None => match &opts.exists_in {
+                Some(exists_in) => {
+                    let sql = subquery_sql(exists_in, &column_sql, &opts.kind, opts.multiple);
+                    let q = subquery(quote! { filter }, &sql);
+                    (q.clone(), q)
+                }
+                None => (q.clone(), q),
+            },
+        };
+
+        let variant = Ident::new(&camel_case(&field.to_string()), field.span());
+        let value_ty = match opts.multiple {
+            true => quote! { Vec<#ty> },
+            false => quote! { #ty },
+        };
+        variants.push(quote! { #cfg #variant(#value_ty), });
+        predicates.push(quote! {
+            #cfg
+            #filter_ident::#variant(filter) => {
+                #predicate_truncate
+                Box::new(::diesel::NullableExpressionMethods::nullable(#predicate))
+            }
+        });
+
+        queries.push(quote! {
+            #cfg
+            if let Some(ref filter) = filters.#field {
+                #truncate
+                query = query.filter(#q);
+            }
+        });
+
+        // Unset filters are short-circuited by a bound `TRUE` rather than left out,
+        // the query then has the same type whatever the filters. Text patterns
+        // can't be bound as `NULL`, an empty one is bound instead
+        let column = &filter_column;
+        let pattern = substring_pattern(quote! { filters.#field.as_deref().unwrap_or_default() });
+        let value = match filter.interval {
+            true => {
+                let value = interval_value(quote! { filters.#field.as_deref() }, column);
+                quote! { ::diesel::NullableExpressionMethods::nullable(#value) }
+            }
+            false => quote! { filters.#field.as_ref() },
+        };
+        let static_q = match (opts.multiple, &opts.kind) {
+            (false, FilterKind::Basic) => quote! {
+                ::diesel::NullableExpressionMethods::nullable(#column).eq(#value)
+            },
+            (false, FilterKind::Gte) => quote! {
+                ::diesel::NullableExpressionMethods::nullable(#column).ge(#value)
+            },
+            (false, FilterKind::Lte) => quote! {
+                ::diesel::NullableExpressionMethods::nullable(#column).le(#value)
+            },
+            (false, FilterKind::Substr) => quote! { #column.like(#pattern) },
+            (false, FilterKind::Insensitive) => quote! {
+                #column.ilike(filters.#field.as_deref().unwrap_or_default())
+            },
+            (false, FilterKind::SubstrInsensitive) => quote! { #column.ilike(#pattern) },
+            (false, FilterKind::Lower) => quote! {
+                lower(#column).eq(lower(filters.#field.as_deref().unwrap_or_default()))
+            },
+            (false, FilterKind::Contains | FilterKind::Overlaps) => {
+                unreachable!("array filters take multiple values")
+            }
+            (false, FilterKind::Bytea) => {
+                let value = hex_value(quote! { filters.#field.as_deref() }, column);
+                quote! {
+                    ::diesel::NullableExpressionMethods::nullable(#column)
+                        .eq(::diesel::NullableExpressionMethods::nullable(#value))
+                }
+            }
+            (false, FilterKind::Prefix) => {
+                hex_prefix(quote! { filters.#field.as_deref() }, &column_sql, false)
+            }
+            (false, FilterKind::Fulltext) => {
+                fulltext(quote! { filters.#field.as_deref() }, &fulltext_sql)
+            }
+            (false, FilterKind::HasFlags) => {
+                has_flags(quote! { filters.#field.as_ref() }, column, &column_sql)
+            }
+            (false, FilterKind::HstoreKey) => {
+                hstore_key(quote! { filters.#field.as_deref() }, &column_sql, false)
+            }
+            (false, FilterKind::HstoreKv) => {
+                hstore_kv(quote! { filters.#field.as_deref() }, &column_sql)
+            }
+            (true, kind) => {
+                let values = values.clone();
+                match kind {
+                    FilterKind::Basic if filter.interval => {
+                        let values = interval_values(values, column);
+                        quote! { #column.eq(any(#values)) }
+                    }
+                    FilterKind::Basic => quote! { #column.eq(any(#values)) },
+                    FilterKind::Substr => {
+                        let patterns = substring_patterns(values);
+                        quote! { #column.like(any(#patterns)) }
+                    }
+                    FilterKind::Insensitive => quote! { #column.ilike(any(#values)) },
+                    FilterKind::SubstrInsensitive => {
+                        let patterns = substring_patterns(values);
+                        quote! { #column.ilike(any(#patterns)) }
+                    }
+                    FilterKind::Lower => {
+                        let values = lower_values(values);
+                        quote! { lower(#column).eq(any(#values)) }
+                    }
+                    FilterKind::Contains => quote! { #column.contains(#values) },
+                    FilterKind::Overlaps => quote! { #column.overlaps_with(#values) },
+                    FilterKind::Bytea => {
+                        let values = hex_values(values, column);
+                        quote! { #column.eq(any(#values)) }
+                    }
+                    FilterKind::Prefix => hex_prefix(values, &column_sql, true),
+                    FilterKind::HstoreKey => hstore_key(values, &column_sql, true),
+                    FilterKind::Gte
+                    | FilterKind::Lte
+                    | FilterKind::Fulltext
+                    | FilterKind::HasFlags
+                    | FilterKind::HstoreKv => {
+                        unreachable!("comparisons, full-text queries, bitmasks and pairs take a single value")
+                    }
+                }
+            }
+        };
+        let static_q = match &opts.tz {
+            Some(tz) => {
+                let dates = match opts.multiple {
+                    true => values,
+                    false => quote! { filters.#field.as_deref() },
+                };
+                local_date(
+                    dates,
+                    quote! { filters.#tz.as_deref() },
+                    &column_sql,
+                    filter.with_time_zone,
+                    opts.multiple,
+                )
+            }
+            None => match &opts.exists_in {
+                Some(exists_in) => {
+                    let sql = subquery_sql(exists_in, &column_sql, &opts.kind, opts.multiple);
+                    let values = match (opts.multiple, &exists_in.column) {
+                        (true, _) => values,
+                        (false, Some(_)) => quote! { filters.#field.as_deref() },
+                        // Booleans and counts
+                        (false, None) => quote! { filters.#field },
+                    };
+                    subquery(values, &sql)
+                }
+                None => static_q,
+            },
+        };
+        let static_q = match opts.max_values {
+            Some(max) if !opts.truncate_values => quote! {
+                ::diesel::BoolExpressionMethods::and(
+                    ::diesel::IntoSql::into_sql::<::diesel::sql_types::Bool>(
+                        filters.#field.as_ref().map_or(true, |values| values.len() <= #max),
+                    ),
+                    #static_q,
+                )
+            },
+            _ => static_q,
+        };
+        static_queries.push(quote! {
+            ::diesel::BoolExpressionMethods::or(
+                ::diesel::IntoSql::into_sql::<::diesel::sql_types::Bool>(filters.#field.is_none()),
+                #static_q,
+            )
+        });
+
+        // The time zone is bound before the dates
+        if let Some(tz) = &opts.tz {
+            let local = match filter.with_time_zone {
+                true => column_sql.clone(),
+                false => format!("timezone('UTC', {})", column_sql),
+            };
+            let (template, bind_ty) = match opts.multiple {
+                false => (
+                    format!("CAST(timezone(COALESCE(${{}}, 'UTC'), {}) AS date) = CAST(${{}} AS date)", local),
+                    quote! { ::diesel::sql_types::Text },
+                ),
+                true => (
+                    format!("CAST(timezone(COALESCE(${{}}, 'UTC'), {}) AS date) = ANY(CAST(${{}} AS date[]))", local),
+                    quote! { ::diesel::sql_types::Array<::diesel::sql_types::Text> },
+                ),
+            };
+            sql_templates.push((field.clone(), numbered_sql(&template)));
+            raw_conditions.push(quote! {
+                #cfg
+                if let Some(ref filter) = filters.#field {
+                    #raw_truncate
+                    params += 2;
+                    conditions.push(format!(#template, params - 1, params));
+                    query = query
+                        .bind::<::diesel::sql_types::Nullable<::diesel::sql_types::Text>, _>(filters.#tz.as_deref())
+                        .bind::<#bind_ty, _>(filter);
+                }
+            });
+            continue;
+        }
+
+        // The key and the value of a pair are bound apart
+        if let FilterKind::HstoreKv = opts.kind {
+            let template = format!("({} -> ${{}}) = ${{}}", column_sql);
+            sql_templates.push((field.clone(), numbered_sql(&template)));
+            raw_conditions.push(quote! {
+                #cfg
+                if let Some(ref filter) = filters.#field {
+                    let pair = filter.split_once(':');
+                    params += 2;
+                    conditions.push(format!(#template, params - 1, params));
+                    query = query
+                        .bind::<::diesel::sql_types::Nullable<::diesel::sql_types::Text>, _>(pair.map(|(key, _)| key))
+                        .bind::<::diesel::sql_types::Nullable<::diesel::sql_types::Text>, _>(pair.map(|(_, value)| value));
+                }
+            });
+            continue;
+        }
+
+        if let Some(exists_in) = &opts.exists_in {
+            let (start, end, bind_ty) =
+                subquery_sql(exists_in, &column_sql, &opts.kind, opts.multiple);
+            let template = format!("{}${{}}{}", start, end);
+            sql_templates.push((field.clone(), numbered_sql(&template)));
+            raw_conditions.push(quote! {
+                #cfg
+                if let Some(ref filter) = filters.#field {
+                    #raw_truncate
+                    params += 1;
+                    conditions.push(format!(#template, params));
+                    query = query.bind::<#bind_ty, _>(filter);
+                }
+            });
+            continue;
+        }
+
+        // The expression of the op has no SQL of its own to append
+        if opts.op.is_some() {
+            let message = format!(
+                "the `{}` filter has a custom op, it can't be appended to raw SQL",
+                field.to_string().trim_start_matches("r#")
+            );
+            raw_conditions.push(quote! {
+                #cfg
+                if filters.#field.is_some() {
+                    return Err(::diesel::result::Error::QueryBuilderError(#message.into()));
+                }
+            });
+            continue;
+        }
+
+        // Every filter binds a single value, numbered after the previous ones
+        let column_ty = quote! { ::diesel::dsl::SqlTypeOf<#column> };
+        let text = quote! { ::diesel::sql_types::Text };
+        let fulltext_template = format!("{}${{}}{}", fulltext_sql.0, fulltext_sql.1);
+        let (template, bind_ty) = match (opts.multiple, &opts.kind) {
+            (false, FilterKind::Basic) if filter.interval => {
+                ("{column} = CAST(${} AS interval)", text)
+            }
+            (false, FilterKind::Gte) if filter.interval => {
+                ("{column} >= CAST(${} AS interval)", text)
+            }
+            (false, FilterKind::Lte) if filter.interval => {
+                ("{column} <= CAST(${} AS interval)", text)
+            }
+            (true, FilterKind::Basic) if filter.interval => (
+                "{column} = ANY(CAST(${} AS interval[]))",
+                quote! { ::diesel::sql_types::Array<#text> },
+            ),
+            (false, FilterKind::Basic) => ("{column} = ${}", column_ty),
+            (false, FilterKind::Gte) => ("{column} >= ${}", column_ty),
+            (false, FilterKind::Lte) => ("{column} <= ${}", column_ty),
+            (false, FilterKind::Substr) => ("{column} LIKE ('%' || ${} || '%')", text),
+            (false, FilterKind::Insensitive) => ("{column} ILIKE ${}", text),
+            (false, FilterKind::SubstrInsensitive) => ("{column} ILIKE ('%' || ${} || '%')", text),
+            (false, FilterKind::Lower) => ("lower({column}) = lower(${})", text),
+            (false, FilterKind::Contains | FilterKind::Overlaps) => {
+                unreachable!("array filters take multiple values")
+            }
+            (false, FilterKind::Bytea) => ("{column} = decode(${}, 'hex')", text),
+            (false, FilterKind::Prefix) => ("position(decode(${}, 'hex') in {column}) = 1", text),
+            (false, FilterKind::Fulltext) => (fulltext_template.as_str(), text),
+            (false, FilterKind::HasFlags) => ("({column} & ${0}) = ${0}", column_ty),
+            (false, FilterKind::HstoreKey) => ("{column} ? ${}", text),
+            (false, FilterKind::HstoreKv) => unreachable!("pairs bind their key and value"),
+            (true, FilterKind::Basic) => (
+                "{column} = ANY(${})",
+                quote! { ::diesel::sql_types::Array<#column_ty> },
+            ),
+            (true, FilterKind::Substr) => (
+                "{column} LIKE ANY(ARRAY(SELECT '%' || value || '%' FROM unnest(${}) AS value))",
+                quote! { ::diesel::sql_types::Array<#text> },
+            ),
+            (true, FilterKind::Insensitive) => (
+                "{column} ILIKE ANY(${})",
+                quote! { ::diesel::sql_types::Array<#text> },
+            ),
+            (true, FilterKind::SubstrInsensitive) => (
+                "{column} ILIKE ANY(ARRAY(SELECT '%' || value || '%' FROM unnest(${}) AS value))",
+                quote! { ::diesel::sql_types::Array<#text> },
+            ),
+            (true, FilterKind::Lower) => (
+                "lower({column}) = ANY(ARRAY(SELECT lower(value) FROM unnest(${}) AS value))",
+                quote! { ::diesel::sql_types::Array<#text> },
+            ),
+            (true, FilterKind::Contains) => ("{column} @> ${}", column_ty),
+            (true, FilterKind::Overlaps) => ("{column} && ${}", column_ty),
+            (true, FilterKind::Bytea) => (
+                "{column} = ANY(ARRAY(SELECT decode(value, 'hex') FROM unnest(${}) AS value))",
+                quote! { ::diesel::sql_types::Array<#text> },
+            ),
+            (
+                true,
+                FilterKind::Gte
+                | FilterKind::Lte
+                | FilterKind::Fulltext
+                | FilterKind::HasFlags
+                | FilterKind::HstoreKv,
+            ) => {
+                unreachable!("comparisons, full-text queries, bitmasks and pairs take a single value")
+            }
+            (true, FilterKind::HstoreKey) => (
+                "{column} ?| ${}",
+                quote! { ::diesel::sql_types::Array<#text> },
+            ),
+            (true, FilterKind::Prefix) => (
+                "EXISTS (SELECT FROM unnest(${}) AS value WHERE position(decode(value, 'hex') in {column}) = 1)",
+                quote! { ::diesel::sql_types::Array<#text> },
+            ),
+        };
+        let template = template.replace("{column}", &column_sql);
+        sql_templates.push((field.clone(), numbered_sql(&template)));
+        raw_conditions.push(quote! {
+            #cfg
+            if let Some(ref filter) = filters.#field {
+                #raw_truncate
+                params += 1;
+                conditions.push(format!(#template, params));
+                query = query.bind::<#bind_ty, _>(filter);
+            }
+        });
+    }
+
+    if has_multiple || !struct_opts.search.is_empty() {
+        uses.push(quote! { use ::diesel::dsl::any; })
+    }
+    if has_lower {
+        // diesel has no `lower` for text columns
+        uses.push(quote! {
+            ::diesel::define_sql_function!(fn lower<ST: ::diesel::sql_types::SingleValue>(x: ST) -> ST);
+        })
+    }
+    let garde_skip = match cfg!(feature = "garde") {
+        true => quote! { #[garde(skip)] },
+        false => quote! {},
+    };
+    if let (true, Some(first)) = (struct_opts.sqlite, struct_opts.search.first()) {
+        return Err(syn::Error::new(
+            first.span(),
+            "`search` matches with `ILIKE ANY`, which SQLite has no counterpart of, remove `sqlite` from `backends`",
+        ));
+    }
+    if let (true, Some(first)) = (struct_opts.mysql, struct_opts.search.first()) {
+        return Err(syn::Error::new(
+            first.span(),
+            "`search` matches with `ILIKE ANY`, which MySQL has no counterpart of, remove `mysql` from `backends`",
+        ));
+    }
+    if let Some((first, rest)) = struct_opts.search.split_first() {
+        let fragment = raw_column(first);
+        test_cases.push(quote! {
+            ("search", { let mut filters = unset(); filters.search = Some("sample".to_owned()); filters }, #fragment)
+        });
+        fields.push(quote! {
+            /// Text searched in any of the search columns, `-term` words exclude the matches
+            #garde_skip
+            #skip_none
+            pub search: Option<String>,
+        });
+        // Matches when any of the columns contains the search, and none of them
+        // contains a `-term` of the search. `IS DISTINCT FROM TRUE` keeps the rows
+        // with `NULL` columns that `NOT` would leave out
+        let searched = |pattern: &proc_macro2::TokenStream| {
+            quote! {
+                ::diesel::NullableExpressionMethods::nullable(#table::#first.ilike(#pattern))
+                    #( .or(::diesel::NullableExpressionMethods::nullable(#table::#rest.ilike(#pattern))) )*
+            }
+        };
+        let pattern = substring_pattern(quote! { text.clone() });
+        let text_predicate = searched(&pattern);
+        let patterns = substring_patterns(quote! { excluded.clone() });
+        let patterns = quote! { any(#patterns) };
+        let excluded_predicate = searched(&patterns);
+        queries.push(quote! {
+            if let Some(ref search) = filters.search {
+                let (text, excluded) = ::diesel_filter::sql::split_search(search);
+                if !text.is_empty() || excluded.is_empty() {
+                    query = query.filter(#text_predicate);
+                }
+                if !excluded.is_empty() {
+                    query = query.filter(::diesel::PgExpressionMethods::is_distinct_from(
+                        #excluded_predicate,
+                        true,
+                    ));
+                }
+            }
+        });
+        // The text is bound once and used for every column, like the excluded terms
+        let columns_template = |predicate: &str| {
+            struct_opts
+                .search
+                .iter()
+                .map(|column| format!("{} {}", raw_column(column), predicate))
+                .collect::<Vec<_>>()
+                .join(" OR ")
+        };
+        let text_template = format!("({})", columns_template("ILIKE ('%' || ${} || '%')"));
+        // Every column compares the same text
+        sql_templates.push((
+            Ident::new("search", Span::call_site()),
+            text_template.replace("${}", "$1"),
+        ));
+        let excluded_template = format!(
+            "({}) IS DISTINCT FROM TRUE",
+            columns_template(
+                "ILIKE ANY(ARRAY(SELECT '%' || value || '%' FROM unnest(${}) AS value))"
+            )
+        );
+        let placeholders = struct_opts
+            .search
+            .iter()
+            .map(|_| quote! { params })
+            .collect::<Vec<_>>();
+        raw_conditions.push(quote! {
+            if let Some(ref search) = filters.search {
+                let (text, excluded) = ::diesel_filter::sql::split_search(search);
+                if !text.is_empty() || excluded.is_empty() {
+                    params += 1;
+                    conditions.push(format!(#text_template, #( #placeholders ),*));
+                    query = query.bind::<::diesel::sql_types::Text, _>(text);
+                }
+                if !excluded.is_empty() {
+                    params += 1;
+                    conditions.push(format!(#excluded_template, #( #placeholders ),*));
+                    query = query.bind::<::diesel::sql_types::Array<::diesel::sql_types::Text>, _>(excluded);
+                }
+            }
+        });
+        static_prelude.push(quote! {
+            let (search_text, search_excluded) =
+                ::diesel_filter::sql::split_search(filters.search.as_deref().unwrap_or_default());
+        });
+        let static_pattern = substring_pattern(quote! { search_text.as_str() });
+        let static_text_predicate = searched(&static_pattern);
+        let static_patterns = substring_patterns(quote! { &search_excluded });
+        let static_patterns = quote! { any(#static_patterns) };
+        let static_excluded_predicate = searched(&static_patterns);
+        static_queries.push(quote! {
+            ::diesel::BoolExpressionMethods::or(
+                ::diesel::IntoSql::into_sql::<::diesel::sql_types::Bool>(
+                    filters.search.is_none() || (search_text.is_empty() && !search_excluded.is_empty()),
+                ),
+                #static_text_predicate,
+            )
+        });
+        // No excluded term matches any row when the search is unset
+        static_queries.push(quote! {
+            ::diesel::PgExpressionMethods::is_distinct_from(#static_excluded_predicate, true)
+        });
+    }
+    if let Some((amount, currency, ty)) = &money {
+        fields.push(quote! {
+            /// Rows with at least this amount in the `currency`, ignored without it
+            #garde_skip
+            #skip_none
+            pub min_amount: Option<#ty>,
+            /// Rows with at most this amount in the `currency`, ignored without it
+            #garde_skip
+            #skip_none
+            pub max_amount: Option<#ty>,
+            /// Currency of the rows, the amounts of different currencies are never compared
+            #garde_skip
+            #skip_none
+            pub currency: Option<String>,
+        });
+        let fragment = raw_column(currency);
+        test_cases.push(quote! {
+            ("currency", { let mut filters = unset(); filters.currency = Some("EUR".to_owned()); filters }, #fragment)
+        });
+        column_checks.push(quote_spanned! {amount.span()=> let _ = #table::#amount; });
+        column_checks.push(quote_spanned! {currency.span()=> let _ = #table::#currency; });
+        let bounds = quote! {
+            if let Some(ref currency) = filters.currency {
+                query = query.filter(#table::#currency.eq(currency));
+                if let Some(min_amount) = filters.min_amount {
+                    query = query.filter(#table::#amount.ge(min_amount));
+                }
+                if let Some(max_amount) = filters.max_amount {
+                    query = query.filter(#table::#amount.le(max_amount));
+                }
+            }
+        };
+        queries.push(bounds.clone());
+        sqlite_queries.push(bounds.clone());
+        mysql_queries.push(bounds);
+        let (amount_sql, currency_sql) = (raw_column(amount), raw_column(currency));
+        let currency_template = format!("{} = ${{}}", currency_sql);
+        let (min_template, max_template) = (
+            format!("{} >= ${{}}", amount_sql),
+            format!("{} <= ${{}}", amount_sql),
+        );
+        // The bounds come after the currency
+        sql_templates.extend(vec![
+            (
+                Ident::new("currency", Span::call_site()),
+                numbered_sql(&currency_template),
+            ),
+            (
+                Ident::new("min_amount", Span::call_site()),
+                format!("{} >= $2", amount_sql),
+            ),
+            (
+                Ident::new("max_amount", Span::call_site()),
+                format!("{} <= $2", amount_sql),
+            ),
+        ]);
+        raw_conditions.push(quote! {
+            if let Some(ref currency) = filters.currency {
+                params += 1;
+                conditions.push(format!(#currency_template, params));
+                query = query.bind::<::diesel::sql_types::Text, _>(currency);
+                if let Some(ref min_amount) = filters.min_amount {
+                    params += 1;
+                    conditions.push(format!(#min_template, params));
+                    query = query.bind::<::diesel::dsl::SqlTypeOf<#table::#amount>, _>(min_amount);
+                }
+                if let Some(ref max_amount) = filters.max_amount {
+                    params += 1;
+                    conditions.push(format!(#max_template, params));
+                    query = query.bind::<::diesel::dsl::SqlTypeOf<#table::#amount>, _>(max_amount);
+                }
+            }
+        });
+        // The bounds are short-circuited along with the currency
+        static_queries.push(quote! {
+            ::diesel::BoolExpressionMethods::or(
+                ::diesel::IntoSql::into_sql::<::diesel::sql_types::Bool>(filters.currency.is_none()),
+                ::diesel::BoolExpressionMethods::and(
+                    ::diesel::BoolExpressionMethods::and(
+                        ::diesel::NullableExpressionMethods::nullable(#table::#currency)
+                            .eq(filters.currency.as_deref()),
+                        ::diesel::BoolExpressionMethods::or(
+                            ::diesel::IntoSql::into_sql::<::diesel::sql_types::Bool>(filters.min_amount.is_none()),
+                            ::diesel::NullableExpressionMethods::nullable(#table::#amount).ge(filters.min_amount),
+                        ),
+                    ),
+                    ::diesel::BoolExpressionMethods::or(
+                        ::diesel::IntoSql::into_sql::<::diesel::sql_types::Bool>(filters.max_amount.is_none()),
+                        ::diesel::NullableExpressionMethods::nullable(#table::#amount).le(filters.max_amount),
+                    ),
+                ),
+            )
+        });
+    }
+    for tz in &tz_params {
+        fields.push(quote! {
+            /// Time zone the dates are compared in, e.g `Europe/Paris`, `UTC` when unset
+            #garde_skip
+            #skip_none
+            pub #tz: Option<String>,
+        });
+    }
+
+    if near {
+        fields.push(quote! {
+            /// Latitude of the point the rows are sorted by the distance to, in degrees
+            #garde_skip
+            #skip_none
+            pub near_lat: Option<f64>,
+            /// Longitude of the point the rows are sorted by the distance to, in degrees
+            #garde_skip
+            #skip_none
+            pub near_lng: Option<f64>,
+        });
+    }
+
+    // Joined columns are sorted by a correlated subquery, the query keeps the type
+    // of the boxed query of the table
+    let sort_arms = sortables
+        .iter()
+        .map(|sortable| {
+            let Sortable {
+                key,
+                column,
+                by,
+                nulls,
+                ..
+            } = sortable;
+            let expr = match by {
+                SortBy::Column => quote! { #table::#column },
+                SortBy::Join(join, on, joined) => {
+                    let sql = format!(
+                        "(SELECT {}.\"{}\" FROM {} WHERE {}.\"{}\" = {})",
+                        join,
+                        joined,
+                        join,
+                        join,
+                        on,
+                        raw_column(column)
+                    );
+                    quote! { ::diesel::dsl::sql::<::diesel::sql_types::Text>(#sql) }
+                }
+                // The haversine of the central angle, the distance grows with it
+                SortBy::Distance(lng) => {
+                    let (lat, lng) = (raw_column(column), raw_column(lng));
+                    let sql = [
+                        format!("(power(sin(radians({} - ", lat),
+                        format!(") / 2), 2) + cos(radians({})) * cos(radians(", lat),
+                        format!(")) * power(sin(radians({} - ", lng),
+                        ") / 2), 2))".to_owned(),
+                    ];
+                    let [a, b, c, d] = &sql;
+                    quote! {
+                        ::diesel::dsl::sql::<::diesel::sql_types::Double>(#a)
+                            .bind::<::diesel::sql_types::Double, _>(near_lat)
+                            .sql(#b)
+                            .bind::<::diesel::sql_types::Double, _>(near_lat)
+                            .sql(#c)
+                            .bind::<::diesel::sql_types::Double, _>(near_lng)
+                            .sql(#d)
+                    }
+                }
+            };
+            let nulls = match nulls {
+                Some(nulls) => quote! { Some(::diesel_filter::sort::NullsOrder::#nulls) },
+                None => quote! { None },
+            };
+            let order = quote! {
+                let expr = #expr;
+                match (term.direction, term.nulls.or(#nulls)) {
+                    (Asc, None) => query.then_order_by(expr.asc()),
+                    (Asc, Some(First)) => query.then_order_by(expr.asc().nulls_first()),
+                    (Asc, Some(Last)) => query.then_order_by(expr.asc().nulls_last()),
+                    (Desc, None) => query.then_order_by(expr.desc()),
+                    (Desc, Some(First)) => query.then_order_by(expr.desc().nulls_first()),
+                    (Desc, Some(Last)) => query.then_order_by(expr.desc().nulls_last()),
+                }
+            };
+            match by {
+                // Without a point there is no distance to sort by
+                SortBy::Distance(lng) => {
+                    column_checks.push(quote_spanned! {column.span()=> let _ = #table::#column; });
+                    column_checks.push(quote_spanned! {lng.span()=> let _ = #table::#lng; });
+                    quote! {
+                        #key => match (filters.near_lat, filters.near_lng) {
+                            (Some(near_lat), Some(near_lng)) => { #order }
+                            _ => query,
+                        },
+                    }
+                }
+                _ => quote! {
+                    #key => { #order }
+                },
+            }
+        })
+        .collect::<Vec<_>>();
+    let sort_keys = sortables
+        .iter()
+        .map(|sortable| sortable.key.as_str())
+        .collect::<Vec<_>>();
+    let (filter, apply_sort) = match sortables.is_empty() {
+        true => (
+            quote! { Self::apply_filters(#table::table.into_boxed(), filters) },
+            quote! {},
+        ),
+        false => {
+            let sort_doc = format!(
+                " Comma separated `key:direction` terms the rows are sorted by, e.g `{}:desc`, among: {}",
+                sort_keys[0],
+                sort_keys.join(", ")
+            );
+            fields.push(quote! {
+                #[doc = #sort_doc]
+                #garde_skip
+                #skip_none
+                pub sort: Option<String>,
+            });
+            (
+                quote! { Self::apply_sort(Self::apply_filters(#table::table.into_boxed(), filters), filters) },
+                quote! {
+                    /// Sorts any boxed query of the table by the `sort` of the filters, the
+                    /// terms with an unknown key or direction are left out
+                    pub fn apply_sort<'query, ST>(
+                        mut query: #table::BoxedQuery<'query, ::diesel::pg::Pg, ST>,
+                        filters: &'query #filter_struct_ident,
+                    ) -> #table::BoxedQuery<'query, ::diesel::pg::Pg, ST> {
+                        use ::diesel::{ExpressionMethods, PgSortExpressionMethods, QueryDsl};
+                        use ::diesel_filter::{sort::NullsOrder::*, SortDirection::*};
+
+                        if let Some(ref sort) = filters.sort {
+                            for term in ::diesel_filter::sort::terms(sort).filter_map(Result::ok) {
+                                query = match term.key {
+                                    #( #sort_arms )*
+                                    _ => query,
+                                };
+                            }
+                        }
+                        query
+                    }
+                },
+            )
+        }
+    };
+
+    if pagination {
+        // GraphQL `Int` is 32 bits, juniper has no input mapping for `i64`
+        let page_ty = match frontends.juniper {
+            true => quote! { i32 },
+            false => quote! { i64 },
+        };
+
+        // Missing parameters get the defaults when deserialized, rather than `None`
+        let opts = pagination_opts.unwrap_or_default();
+        let mut page_attrs = vec![];
+        let mut per_page_attrs = vec![];
+        let defaults = [
+            (&opts.default_page, "default_page", &mut page_attrs),
+            (
+                &opts.default_per_page,
+                "default_per_page",
+                &mut per_page_attrs,
+            ),
+        ];
+        for (default, name, attrs) in defaults {
+            if let Some(default) = default {
+                if frontends.rocket {
+                    attrs.push(quote! { #[field(default = Some(#default))] });
+                }
+                if serde {
+                    let helper = Ident::new(name, default.span());
+                    let path = format!("{}::{}", filter_struct_ident, helper);
+                    helpers.push(quote! {
+                        #[doc(hidden)]
+                        fn #helper() -> Option<#page_ty> {
+                            Some(#default)
+                        }
+                    });
+                    attrs.push(quote! { #[serde(default = #path)] });
+                }
+            }
+        }
+        let page_doc = match &opts.default_page {
+            Some(page) => format!(" The page to load, starting at 1, {} by default", page),
+            None => " The page to load, starting at 1, the first page by default".to_owned(),
+        };
+        let per_page_doc = format!(
+            " The number of rows per page, {} by default",
+            opts.default_per_page
+                .as_ref()
+                .map_or_else(|| "10".to_owned(), |per_page| per_page.to_string())
+        );
+
+        fields.push(quote! {
+            #[doc = #page_doc]
+            #( #page_attrs )*
+            #garde_skip
+            #skip_none
+            pub page: Option<#page_ty>,
+            #[doc = #per_page_doc]
+            #( #per_page_attrs )*
+            #garde_skip
+            #skip_none
+            pub per_page: Option<#page_ty>,
+        });
+    }
+    let helpers_impl = if helpers.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            impl #filter_struct_ident {
+                #( #helpers )*
+            }
+        }
+    };
+
+    let extractor_impl = match frontends.axum {
+        true => {
+            let operation_input = match frontends.aide {
+                true => quote! {
+                    impl ::aide::OperationInput for #filter_struct_ident {
+                        fn operation_input(
+                            ctx: &mut ::aide::generate::GenContext,
+                            operation: &mut ::aide::openapi::Operation,
+                        ) {
+                            <::axum::extract::Query<Self> as ::aide::OperationInput>::operation_input(ctx, operation)
+                        }
+                    }
+                },
+                false => quote! {},
+            };
+
+            quote! {
+                impl<S> ::axum::extract::FromRequestParts<S> for #filter_struct_ident
+                where
+                    S: Send + Sync,
+                {
+                    type Rejection = ::diesel_filter::FilterRejection;
+
+                    async fn from_request_parts(
+                        parts: &mut ::axum::http::request::Parts,
+                        _state: &S,
+                    ) -> Result<Self, Self::Rejection> {
+                        ::diesel_filter::FilterRejection::from_query(parts.uri.query())
+                    }
+                }
+
+                #operation_input
+            }
+        }
+        false => quote! {},
+    };
+
+    let warp_impl = match frontends.warp {
+        true => quote! {
+        impl #filter_struct_ident {
+            pub fn filters_query() -> impl ::warp::Filter<Extract = (#filter_struct_ident,), Error = ::warp::Rejection> + Clone {
+                ::warp::query::<#filter_struct_ident>()
+            }
+        }
+        },
+        false => quote! {},
+    };
+
+    // Every selected framework contributes its derives to the same struct, so
+    // crates enabling different features in a workspace still compile
+    let lenient = frontends.rocket && struct_opts.lenient;
+    let mut derives = vec![];
+    if frontends.rocket && !lenient {
+        derives.push(quote! { ::rocket::FromForm });
+    }
+    if serde {
+        derives.push(quote! { ::serde::Deserialize });
+        derives.push(quote! { ::serde::Serialize });
+    }
+    if frontends.aide {
+        derives.push(quote! { ::schemars::JsonSchema });
+    }
+    if frontends.poem {
+        derives.push(quote! { ::poem_openapi::Object });
+    }
+    if frontends.salvo {
+        derives.push(quote! { ::salvo::oapi::ToParameters });
+    }
+    if frontends.juniper {
+        derives.push(quote! { ::juniper::GraphQLInputObject });
+    }
+    if cfg!(feature = "validation") {
+        derives.push(quote! { ::validator::Validate });
+    }
+    if cfg!(feature = "garde") {
+        derives.push(quote! { ::garde::Validate });
+    }
+    if cfg!(feature = "ts") {
+        derives.push(quote! { ::ts_rs::TS });
+    }
+    derives.push(quote! { ::std::fmt::Debug });
+
+    let mut struct_attrs = vec![];
+    if frontends.salvo {
+        struct_attrs.push(quote! { #[salvo(parameters(default_parameter_in = Query))] });
+    }
+    if cfg!(feature = "ts") {
+        struct_attrs.push(quote! { #[ts(export)] });
+    }
+
+    let filters_struct = match lenient {
+        // The form is derived on a hidden copy of the struct, parsed with
+        // `Options::Lenient` whatever the strategy requested by the caller
+        true => {
+            let mut public_fields: syn::FieldsNamed =
+                syn::parse2(quote! { { #( #fields )* } }).unwrap();
+            let mut form_fields = public_fields.clone();
+            for field in public_fields.named.iter_mut() {
+                field.attrs.retain(|attr| !attr.path().is_ident("field"));
+            }
+            for field in form_fields.named.iter_mut() {
+                field.attrs.retain(|attr| attr.path().is_ident("field"));
+            }
+
+            quote! {
+                #[derive(#( #derives ),*)]
+                #( #struct_attrs )*
+                pub struct #filter_struct_ident #public_fields
+
+                const _: () = {
+                    #[derive(::rocket::FromForm)]
+                    pub struct LenientForm #form_fields
+
+                    #[::rocket::async_trait]
+                    impl<'v> ::rocket::form::FromForm<'v> for #filter_struct_ident {
+                        type Context = <LenientForm as ::rocket::form::FromForm<'v>>::Context;
+
+                        fn init(_opts: ::rocket::form::Options) -> Self::Context {
+                            <LenientForm as ::rocket::form::FromForm<'v>>::init(::rocket::form::Options::Lenient)
+                        }
+
+                        fn push_value(ctxt: &mut Self::Context, field: ::rocket::form::ValueField<'v>) {
+                            <LenientForm as ::rocket::form::FromForm<'v>>::push_value(ctxt, field)
+                        }
+
+                        async fn push_data(ctxt: &mut Self::Context, field: ::rocket::form::DataField<'v, '_>) {
+                            <LenientForm as ::rocket::form::FromForm<'v>>::push_data(ctxt, field).await
+                        }
+
+                        fn finalize(ctxt: Self::Context) -> ::rocket::form::Result<'v, Self> {
+                            let form = <LenientForm as ::rocket::form::FromForm<'v>>::finalize(ctxt)?;
+                            Ok(Self {
+                                #( #field_cfgs #field_names: form.#field_names, )*
+                            })
+                        }
+                    }
+                };
+            }
+        }
+        false => quote! {
+            #[derive(#( #derives ),*)]
+            #( #struct_attrs )*
+            pub struct #filter_struct_ident {
+                #( #fields )*
+            }
+        },
+    };
+
+    let (prelude, query) = match struct_opts.static_dispatch {
+        true => (
+            quote! {
+                #( #uses )*
+                #( #static_prelude )*
+            },
+            quote! { #table::table #( .filter(#static_queries) )* },
+        ),
+        false => (quote! {}, quote! { Self::filter(filters) }),
+    };
+    let limit = match &struct_opts.max_rows {
+        Some(max_rows) => quote! { .limit(#max_rows) },
+        None => quote! {},
+    };
+    let link_header_impl = match pagination {
+        true => quote! {
+            impl #filter_struct_ident {
+                /// The `Link` header of the page among the `total` rows matching the filters,
+                /// with the `first`, `prev`, `next` and `last` pages of `base_url`
+                pub fn link_header(&self, base_url: &str, total: i64) -> String {
+                    let filters = self;
+                    ::diesel_filter::PageInfo::new(total, #per_page)
+                        .with_page(#page)
+                        .link_header(base_url, #per_page)
+                }
+            }
+        },
+        false => quote! {},
+    };
+    let (output, load) = match pagination {
+        true => (
+            quote! { (Vec<#loaded>, i64) },
+            quote! {{
+                #prelude
+                #query
+                  .paginate(#page)
+                  .per_page(#per_page)
+                  .load_and_count::<#loaded>(conn)
+            }},
+        ),
+        false => (
+            quote! { Vec<#loaded> },
+            quote! {{
+                #prelude
+                #query #limit .load::<#loaded>(conn)
+            }},
+        ),
+    };
+
+    // `SET LOCAL` lasts until the end of the transaction, the query is run in one
+    let with_timeout = |query: proc_macro2::TokenStream| match &struct_opts.timeout_ms {
+        Some(timeout) => {
+            let set = format!("SET LOCAL statement_timeout = {}", timeout.base10_digits());
+            quote! {
+                ::diesel::Connection::transaction(conn, |conn| {
+                    ::diesel::RunQueryDsl::execute(::diesel::sql_query(#set), conn)?;
+                    #query
+                })
+            }
+        }
+        None => query,
+    };
+    let load = with_timeout(load);
+
+    let load = match struct_opts.log_sql {
+        Some(keep) => quote! {{
+            if ::diesel_filter::__log::log_enabled!(target: "diesel_filter", ::diesel_filter::__log::Level::Debug) {
+                ::diesel_filter::__log::debug!(
+                    target: "diesel_filter",
+                    "{}",
+                    ::diesel_filter::sql::redact_binds(&Self::filter_sql(filters), #keep)
+                );
+            }
+            #load
+        }},
+        None => load,
+    };
+
+    let load = match cfg!(feature = "metrics") {
+        true => {
+            let names = field_names
+                .iter()
+                .map(|field| field.to_string().trim_start_matches("r#").to_owned());
+            // The bits keep their position in the builds leaving out `cfg` filters
+            let bits = field_names
+                .iter()
+                .zip(&field_cfgs)
+                .take(64)
+                .enumerate()
+                .map(|(i, (field, cfg))| quote! { #cfg { active |= (filters.#field.is_some() as u64) << #i; } });
+            quote! {{
+                let start = ::std::time::Instant::now();
+                let result = #load;
+                let mut active = 0;
+                #( #bits )*
+                ::diesel_filter::metrics::record(::diesel_filter::metrics::FilterEvent {
+                    table: #table_label,
+                    fields: &[#( #names ),*],
+                    active,
+                    elapsed: start.elapsed(),
+                });
+                result
+            }}
+        }
+        false => load,
+    };
+
+    let load = match cfg!(feature = "tracing") {
+        true => {
+            let names = field_names
+                .iter()
+                .map(|field| field.to_string().trim_start_matches("r#").to_owned());
+            let rows = match pagination {
+                true => quote! { output.0.len() },
+                false => quote! { output.len() },
+            };
+            // Database client span semantic conventions of OpenTelemetry
+            let otel = match cfg!(feature = "otel") {
+                true => {
+                    let name = format!("SELECT {}", table_label);
+                    quote! {
+                        "otel.name" = #name,
+                        "otel.kind" = "client",
+                        "db.system" = "postgresql",
+                        "db.operation" = "SELECT",
+                        "db.sql.table" = #table_label,
+                    }
+                }
+                false => quote! {},
+            };
+            quote! {{
+                let span = ::tracing::info_span!(
+                    "diesel_filter.filtered",
+                    table = #table_label,
+                    filters = ::tracing::field::Empty,
+                    rows = ::tracing::field::Empty,
+                    elapsed_ms = ::tracing::field::Empty,
+                    #otel
+                );
+                let _enter = span.enter();
+                let active = [#( #field_cfgs (#names, filters.#field_names.is_some()) ),*]
+                    .iter()
+                    .filter(|(_, active)| *active)
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                span.record("filters", active.as_str());
+
+                let start = ::std::time::Instant::now();
+                let result = #load;
+                span.record("elapsed_ms", start.elapsed().as_millis() as u64);
+                if let Ok(output) = &result {
+                    span.record("rows", #rows);
+                }
+                result
+            }}
+        }
+        false => load,
+    };
+
+    let mut validators: Vec<proc_macro2::TokenStream> = vec![
+        #[cfg(feature = "validation")]
+        quote! { ::validator::Validate::validate(filters).map_err(::diesel_filter::Error::Validation)?; },
+        #[cfg(feature = "garde")]
+        quote! { ::garde::Validate::validate(filters).map_err(|err| ::diesel_filter::Error::Garde(Box::new(err)))?; },
+    ];
+    // A page below 1 would be a negative offset, rejected by the database
+    if pagination {
+        validators.push(quote! {
+            let page = #page.unwrap_or(1);
+            let per_page = #per_page.unwrap_or(::diesel_filter::DEFAULT_PER_PAGE);
+            if page < 1 || per_page < 1 {
+                return Err(::diesel_filter::Error::Pagination { page, per_page });
+            }
+        });
+    }
+    // Thousands of values make enormous queries, they are rejected before running them
+    for (field, max) in &limited_values {
+        let name = field.to_string().trim_start_matches("r#").to_owned();
+        let cfg = cfg_of(field);
+        validators.push(quote! {
+            #cfg
+            if let Some(ref values) = filters.#field {
+                if values.len() > #max {
+                    return Err(::diesel_filter::Error::TooManyValues {
+                        filter: #name,
+                        values: values.len(),
+                        max: #max,
+                    });
+                }
+            }
+        });
+    }
+    // `diesel_filter::Error` comes with the validation and pagination features
+    if !validators.is_empty() && !sortables.is_empty() {
+        validators.push(quote! {
+            if let Some(ref sort) = filters.sort {
+                ::diesel_filter::sort::check(sort, &[#( #sort_keys ),*]).map_err(::diesel_filter::Error::Sort)?;
+            }
+        });
+    }
+
+    // `diesel::dsl::any` of the statically typed query
+    let allow_deprecated = match struct_opts.static_dispatch {
+        true => quote! { #[allow(deprecated)] },
+        false => quote! {},
+    };
+    let validate_filters = match validators.is_empty() {
+        true => quote! {},
+        false => quote! {
+            /// The checks `filtered` runs before the query with `#[diesel_filter(validate)]`,
+            /// without a connection, e.g to reject a request before borrowing one from the pool
+            pub fn validate_filters(filters: &#filter_struct_ident) -> Result<(), ::diesel_filter::Error> {
+                #( #validators )*
+                Ok(())
+            }
+        },
+    };
+    let filtered = match (loadable, struct_opts.validate && !validators.is_empty()) {
+        (false, _) => quote! {},
+        (true, true) => quote! {
+            #allow_deprecated
+            pub fn filtered(filters: &#filter_struct_ident, conn: &mut ::diesel::pg::PgConnection) -> Result<#output, ::diesel_filter::Error> {
+                Self::validate_filters(filters)?;
+                Ok(#load?)
+            }
+        },
+        (true, false) => {
+            // The rejected values are a failure of the query without the validation
+            let rejected = limited_values.iter().map(|(field, max)| {
+                let name = field.to_string().trim_start_matches("r#").to_owned();
+                let cfg = cfg_of(field);
+                quote! {
+                    #cfg
+                    if let Some(ref values) = filters.#field {
+                        if values.len() > #max {
+                            return Err(::diesel::result::Error::QueryBuilderError(Box::new(
+                                ::diesel_filter::Error::TooManyValues {
+                                    filter: #name,
+                                    values: values.len(),
+                                    max: #max,
+                                },
+                            )));
+                        }
+                    }
+                }
+            });
+            quote! {
+                #allow_deprecated
+                pub fn filtered(filters: &#filter_struct_ident, conn: &mut ::diesel::pg::PgConnection) -> Result<#output, ::diesel::result::Error> {
+                    #( #rejected )*
+                    #load
+                }
+            }
+        }
+    };
+
+    // One more row than `max_rows` tells whether some were left out
+    let filtered_truncated = match &struct_opts.max_rows {
+        Some(max_rows) if loadable && !pagination => {
+            let more = max_rows.base10_parse::<i64>()? + 1;
+            let load = with_timeout(quote! {{
+                #prelude
+                #query.limit(#more).load::<#loaded>(conn)
+            }});
+            let (error, validate) = match struct_opts.validate && !validators.is_empty() {
+                true => (
+                    quote! { ::diesel_filter::Error },
+                    quote! { Self::validate_filters(filters)?; },
+                ),
+                false => (quote! { ::diesel::result::Error }, quote! {}),
+            };
+            quote! {
+                /// `filtered`, with whether the filters matched more than the `max_rows` rows it loads
+                #allow_deprecated
+                pub fn filtered_truncated(filters: &#filter_struct_ident, conn: &mut ::diesel::pg::PgConnection) -> Result<(Vec<#loaded>, bool), #error> {
+                    #validate
+                    let mut rows = #load?;
+                    let truncated = rows.len() > #max_rows;
+                    rows.truncate(#max_rows);
+                    Ok((rows, truncated))
+                }
+            }
+        }
+        _ => quote! {},
+    };
+
+    // Every chunk is a query of its own, their rows are appended in the order of the chunks
+    let filtered_chunked = match &chunked {
+        Some((field, size, cfg)) if loadable => {
+            let load = with_timeout(quote! {{
+                #prelude
+                #query #limit .load::<#loaded>(conn)
+            }});
+            let max_rows = match &struct_opts.max_rows {
+                Some(max_rows) => quote! {
+                    if rows.len() >= #max_rows {
+                        rows.truncate(#max_rows);
+                        break;
+                    }
+                },
+                None => quote! {},
+            };
+            let (others, other_cfgs): (Vec<_>, Vec<_>) = field_names
+                .iter()
+                .zip(&field_cfgs)
+                .filter(|(name, _)| *name != field)
+                .unzip();
+            quote! {
+                /// `filtered` with a query per `chunk_size` values of the chunked filter, e.g to
+                /// look thousands of ids up, with the rows of all of them. The rows are sorted
+                /// within every chunk, and the page, if any, is left out
+                #cfg
+                #allow_deprecated
+                pub fn filtered_chunked(filters: &#filter_struct_ident, conn: &mut ::diesel::pg::PgConnection) -> Result<Vec<#loaded>, ::diesel::result::Error> {
+                    let values = match &filters.#field {
+                        Some(values) if values.len() > #size => values,
+                        _ => return #load,
+                    };
+                    let mut rows = vec![];
+                    for chunk in values.chunks(#size) {
+                        let filters = &#filter_struct_ident {
+                            #field: Some(chunk.to_vec()),
+                            #( #other_cfgs #others: filters.#others.clone(), )*
+                        };
+                        rows.extend(#load?);
+                        #max_rows
+                    }
+                    Ok(rows)
+                }
+            }
+        }
+        _ => quote! {},
+    };
+
+    let cache = match cfg!(feature = "cache") {
+        true => {
+            let error = match struct_opts.validate && !validators.is_empty() {
+                true => quote! { ::diesel_filter::Error },
+                false => quote! { ::diesel::result::Error },
+            };
+            let ttl = match &struct_opts.cache_ttl {
+                Some(ttl) => quote! { Some(::std::time::Duration::from_secs(#ttl)) },
+                None => quote! { None },
+            };
+            let page = match pagination {
+                true => quote! { format!(" -- page: {:?}, per_page: {:?}", #page, #per_page) },
+                false => quote! { "" },
+            };
+            let filtered_cached = match loadable {
+                true => quote! {
+                    /// `filtered`, unless `cache` has the output for the `cache_key` of the filters
+                    pub fn filtered_cached<K: ::diesel_filter::FilterCache<#output>>(filters: &#filter_struct_ident, conn: &mut ::diesel::pg::PgConnection, cache: &K) -> Result<#output, #error> {
+                        let key = Self::cache_key(filters);
+                        if let Some(output) = cache.get(&key) {
+                            return Ok(output);
+                        }
+                        let output = Self::filtered(filters, conn)?;
+                        cache.set(&key, &output, #ttl);
+                        Ok(output)
+                    }
+                },
+                false => quote! {},
+            };
+            quote! {
+                /// The key of the rows `filtered` loads for the filters: the SQL of `filter`
+                /// with its bound values, and the page
+                pub fn cache_key(filters: &#filter_struct_ident) -> String {
+                    format!("{}{}", Self::filter_sql(filters), #page)
+                }
+
+                #filtered_cached
+            }
+        }
+        false => quote! {},
+    };
+
+    // Development only, the query is run to time it
+    let explained = match pagination {
+        true => quote! { Self::filter(filters).paginate(#page).per_page(#per_page) },
+        false => quote! { Self::filter(filters) #limit },
+    };
+    let explain = match cfg!(feature = "explain") {
+        true => quote! {
+            /// The `EXPLAIN (ANALYZE, BUFFERS)` plan of the query of `filtered`, e.g to check from a test
+            /// that a combination of filters uses an index
+            #[cfg(debug_assertions)]
+            pub fn filtered_explain(filters: &#filter_struct_ident, conn: &mut ::diesel::pg::PgConnection) -> Result<String, ::diesel::result::Error> {
+                ::diesel_filter::explain::Explain::new(#explained).plan(conn)
+            }
+        },
+        false => quote! {},
+    };
+
+    let count = with_timeout(quote! {
+        Self::apply_filters(
+            #table::table.select(::diesel::dsl::count_star()).into_boxed(),
+            filters,
+        )
+        .get_result(conn)
+    });
+
+    let replica = match cfg!(feature = "replica") {
+        true => {
+            let error = match struct_opts.validate && !validators.is_empty() {
+                true => quote! { ::diesel_filter::Error },
+                false => quote! { ::diesel::result::Error },
+            };
+            let filtered_on = match loadable {
+                true => quote! {
+                    /// `filtered` on the connection `conns` reads from, e.g a replica
+                    pub fn filtered_on<C: ::diesel_filter::ReadConnection>(filters: &#filter_struct_ident, conns: &mut C) -> Result<#output, #error> {
+                        Self::filtered(filters, conns.read_conn())
+                    }
+                },
+                false => quote! {},
+            };
+            quote! {
+                #filtered_on
+
+                /// `count_filtered` on the connection `conns` reads from, e.g a replica
+                pub fn count_filtered_on<C: ::diesel_filter::ReadConnection>(filters: &#filter_struct_ident, conns: &mut C) -> Result<i64, ::diesel::result::Error> {
+                    Self::count_filtered(filters, conns.read_conn())
+                }
+            }
+        }
+        false => quote! {},
+    };
+
+    // Keys in the order of `primary_key(...)`, not generated when a key isn't a field
+    let primary_key_fields = primary_key
+        .iter()
+        .map(|key| primary_key_fields.iter().find(|(name, _, _)| name == key))
+        .collect::<Option<Vec<_>>>();
+    let filtered_page_info = match pagination {
+        true => quote! {
+            /// The number of rows matching the filters and of pages of `per_page` rows,
+            /// counted without loading them, e.g for `HEAD` requests
+            pub fn filtered_page_info(filters: &#filter_struct_ident, conn: &mut ::diesel::pg::PgConnection) -> Result<::diesel_filter::PageInfo, ::diesel::result::Error> {
+                let total = Self::count_filtered(filters, conn)?;
+                Ok(::diesel_filter::PageInfo::new(total, #per_page).with_page(#page))
+            }
+        },
+        false => quote! {},
+    };
+
+    let filtered_latest = match (&struct_opts.latest_per, &struct_opts.latest_by) {
+        (Some(group), Some(by)) => {
+            let key = match primary_key_fields.as_deref() {
+                Some([(_, key, _)]) if loadable => key,
+                _ => {
+                    return Err(syn::Error::new(
+                        group.span(),
+                        "`latest_per` selects the rows by primary key, the model needs a single column key among its fields and owned fields",
+                    ))
+                }
+            };
+            column_checks.push(quote_spanned! {group.span()=> let _ = #table::#group; });
+            column_checks.push(quote_spanned! {by.span()=> let _ = #table::#by; });
+            // Boxed queries have no `distinct_on`, the keys are selected with raw SQL
+            let select = format!("DISTINCT ON ({}) {}", raw_column(group), raw_column(key));
+            let load = match pagination {
+                true => quote! {
+                    .paginate(#page)
+                    .per_page(#per_page)
+                    .load_and_count::<#loaded>(conn)
+                },
+                false => quote! { #limit .load::<#loaded>(conn) },
+            };
+            quote! {
+                /// The newest row of every group matching the filters, e.g the last reading of
+                /// every device, ordered by group
+                pub fn filtered_latest(filters: &#filter_struct_ident, conn: &mut ::diesel::pg::PgConnection) -> Result<#output, ::diesel::result::Error> {
+                    use ::diesel::PgSortExpressionMethods;
+
+                    let latest = Self::apply_filters(
+                        #table::table
+                            .select(::diesel::dsl::sql::<::diesel::dsl::SqlTypeOf<#table::#key>>(#select))
+                            .into_boxed(),
+                        filters,
+                    )
+                    .order_by((#table::#group.asc(), #table::#by.desc().nulls_last()));
+                    #table::table
+                        .filter(#table::#key.eq_any(latest))
+                        .order_by(#table::#group.asc())
+                        #load
+                }
+            }
+        }
+        _ => quote! {},
+    };
+
+    let filtered_union = match struct_opts.union.first() {
+        Some(first) if loadable => {
+            if struct_opts.schema.is_some() {
+                return Err(syn::Error::new_spanned(
+                    first,
+                    "`union` shadows the table with the union of the tables, it can't be in another schema",
+                ));
+            }
+            let tables = struct_opts
+                .union
+                .iter()
+                .map(|path| {
+                    let module = match path.get_ident() {
+                        Some(name) => quote! { #schema_module::#name },
+                        None => quote! { #path },
+                    };
+                    let name = &path.segments.last().unwrap().ident;
+                    column_checks.push(quote_spanned! {name.span()=> let _ = #module::table; });
+                    name.to_string()
+                })
+                .collect::<Vec<_>>();
+            let table_name = table_name.to_string();
+            let load = match pagination {
+                true => quote! {
+                    .paginate(#page)
+                    .per_page(#per_page)
+                    .load_and_count::<#loaded>(conn)
+                },
+                false => quote! { .load::<#loaded>(conn) },
+            };
+            // The limit is applied to the rows of the union, not to each table
+            let unioned = match pagination {
+                true => quote! { Self::filter(filters) },
+                false => quote! { Self::filter(filters) #limit },
+            };
+            quote! {
+                /// `filtered` on the rows of the tables of `union(...)` rather than the ones of
+                /// the table, e.g to search archived partitions
+                pub fn filtered_union(filters: &#filter_struct_ident, conn: &mut ::diesel::pg::PgConnection) -> Result<#output, ::diesel::result::Error> {
+                    ::diesel_filter::union::UnionAll::new(#unioned, #table_name, &[#( #tables ),*])
+                        #load
+                }
+            }
+        }
+        _ => quote! {},
+    };
+
+    // The values of the cursor are bound as text, read by the database as the type
+    // of their column, and compared in the order of the `ORDER BY`
+    let filtered_after = match &primary_key_fields {
+        Some(keys) if loadable && !sortables.is_empty() => {
+            let from = match &struct_opts.schema {
+                Some(schema) => format!("\"{}\".\"{}\"", schema, table_name),
+                None => format!("\"{}\"", table_name),
+            };
+            // `(json_populate_record(NULL::"posts", json_build_object('id', $1)))."id"`
+            let typed = |column: &Ident| {
+                let column = column.to_string().trim_start_matches("r#").to_owned();
+                (
+                    format!(
+                        "(json_populate_record(NULL::{}, json_build_object('{}', ",
+                        from, column
+                    ),
+                    format!("))).\"{}\"", column),
+                )
+            };
+            let mut arms = vec![];
+            let mut value_arms = vec![];
+            for sortable in &sortables {
+                let key = &sortable.key;
+                let column = &sortable.column;
+                let nulls = match &sortable.nulls {
+                    Some(nulls) => quote! { Some(::diesel_filter::sort::NullsOrder::#nulls) },
+                    None => quote! { None },
+                };
+                // The cursor holds the foreign key of a joined column
+                let (row, (prefix, suffix), nullable, joined) = match &sortable.by {
+                    SortBy::Column => (raw_column(column), typed(column), sortable.nullable, false),
+                    SortBy::Join(join, on, joined) => {
+                        let select = format!(
+                            "(SELECT {}.\"{}\" FROM {} WHERE {}.\"{}\" = ",
+                            join, joined, join, join, on
+                        );
+                        let (prefix, suffix) = typed(column);
+                        (
+                            format!("{}{})", select, raw_column(column)),
+                            (format!("{}{}", select, prefix), format!("{})", suffix)),
+                            true,
+                            true,
+                        )
+                    }
+                    SortBy::Distance(_) => {
+                        let message = format!("the rows sorted by `{}` have no cursor", key);
+                        arms.push(quote! {
+                            #key => return Err(::diesel::result::Error::QueryBuilderError(#message.into())),
+                        });
+                        continue;
+                    }
+                };
+                arms.push(quote! {
+                    #key => (#row, (#prefix, #suffix), #nulls, #nullable, #joined),
+                });
+                let field = &sortable.field;
+                value_arms.push(match sortable.nullable {
+                    true => quote! { #key => row.#field.as_ref().map(ToString::to_string), },
+                    false => quote! { #key => Some(row.#field.to_string()), },
+                });
+            }
+            let names = keys.iter().map(|(name, _, _)| name).collect::<Vec<_>>();
+            let columns = keys.iter().map(|(_, column, _)| column).collect::<Vec<_>>();
+            let rows = columns.iter().map(|column| raw_column(column));
+            let (prefixes, suffixes): (Vec<_>, Vec<_>) =
+                columns.iter().map(|column| typed(column)).unzip();
+            quote! {
+                /// Loads up to `limit` rows in the order of the `sort` of the filters, after the
+                /// row of `cursor`, with the cursor of the next page when there are more rows.
+                /// The cursor keeps the sort, the next pages need only the other filters
+                pub fn filtered_after(
+                    filters: &#filter_struct_ident,
+                    cursor: Option<&str>,
+                    limit: i64,
+                    conn: &mut ::diesel::pg::PgConnection,
+                ) -> Result<(Vec<#loaded>, Option<String>), ::diesel::result::Error> {
+                    use ::diesel::{QueryDsl, RunQueryDsl};
+                    use ::diesel_filter::{
+                        cursor::{Cursor, CursorColumn},
+                        sort::NullsOrder::{self, *},
+                        SortDirection::{self, *},
+                    };
+
+                    /// The condition of `cursor::after`, its parts and the values bound after them
+                    struct After(Vec<(String, Option<String>)>);
+                    impl ::diesel::expression::Expression for After {
+                        type SqlType = ::diesel::sql_types::Bool;
+                    }
+                    impl<QS> ::diesel::expression::AppearsOnTable<QS> for After {}
+                    impl<QS> ::diesel::expression::SelectableExpression<QS> for After {}
+                    impl ::diesel::expression::ValidGrouping<()> for After {
+                        type IsAggregate = ::diesel::expression::is_aggregate::Never;
+                    }
+                    impl ::diesel::query_builder::QueryId for After {
+                        type QueryId = ();
+                        const HAS_STATIC_QUERY_ID: bool = false;
+                    }
+                    impl ::diesel::query_builder::QueryFragment<::diesel::pg::Pg> for After {
+                        fn walk_ast<'b>(
+                            &'b self,
+                            mut out: ::diesel::query_builder::AstPass<'_, 'b, ::diesel::pg::Pg>,
+                        ) -> ::diesel::QueryResult<()> {
+                            for (sql, value) in &self.0 {
+                                out.push_sql(sql);
+                                if let Some(value) = value {
+                                    out.push_bind_param::<::diesel::sql_types::Text, _>(value)?;
+                                }
+                            }
+                            Ok(())
+                        }
+                    }
+
+                    let invalid = |err: &dyn ::std::fmt::Display| {
+                        ::diesel::result::Error::QueryBuilderError(err.to_string().into())
+                    };
+                    let cursor = cursor
+                        .map(str::parse::<Cursor>)
+                        .transpose()
+                        .map_err(|err| invalid(&err))?;
+                    let sort = match &cursor {
+                        Some(cursor) => cursor.sort.clone(),
+                        None => filters.sort.clone().unwrap_or_default(),
+                    };
+                    // The columns of the sort, then the primary key in the direction of the last one.
+                    // The `NULL`s are where `apply_sort` puts them, last in ascending order by default
+                    let mut keys = vec![];
+                    let mut columns: Vec<(&str, (&str, &str), SortDirection, Option<NullsOrder>, bool)> = vec![];
+                    for term in ::diesel_filter::sort::terms(&sort) {
+                        let term = term.map_err(|err| invalid(&err))?;
+                        let (row, cursor, nulls, nullable, joined): (_, _, Option<NullsOrder>, bool, bool) = match term.key {
+                            #( #arms )*
+                            key => return Err(invalid(&format!("invalid sort key `{}`", key))),
+                        };
+                        let nulls = match (nullable, term.direction) {
+                            (false, _) => None,
+                            (true, Asc) => Some(term.nulls.or(nulls).unwrap_or(Last)),
+                            (true, Desc) => Some(term.nulls.or(nulls).unwrap_or(First)),
+                        };
+                        keys.push(term.key);
+                        columns.push((row, cursor, term.direction, nulls, joined));
+                    }
+                    let direction = columns.last().map_or(Asc, |(_, _, direction, _, _)| *direction);
+                    #( columns.push((#rows, (#prefixes, #suffixes), direction, None, false)); )*
+                    let order = columns
+                        .iter()
+                        .map(|(row, _, direction, nulls, _)| match nulls {
+                            Some(First) => format!("{} {} NULLS FIRST", row, direction),
+                            Some(Last) => format!("{} {} NULLS LAST", row, direction),
+                            None => format!("{} {}", row, direction),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    let mut query = Self::apply_filters(#table::table.into_boxed(), filters)
+                        .order_by(::diesel::dsl::sql::<::diesel::sql_types::Text>(&order))
+                        .limit(limit + 1);
+                    if let Some(cursor) = &cursor {
+                        // A value for every column, a `NULL` only where the column has some
+                        let malformed = columns.len() != cursor.values.len()
+                            || columns
+                                .iter()
+                                .zip(&cursor.values)
+                                .any(|((_, _, _, nulls, _), value)| nulls.is_none() && value.is_none());
+                        if malformed {
+                            return Err(invalid(&::diesel_filter::cursor::CursorError(cursor.to_string())));
+                        }
+                        let columns = columns
+                            .iter()
+                            .zip(&cursor.values)
+                            .map(|(&(row, cursor, direction, nulls, joined), value)| CursorColumn {
+                                row,
+                                cursor,
+                                value: value.as_deref(),
+                                direction,
+                                nulls,
+                                joined,
+                            })
+                            .collect::<Vec<_>>();
+                        let after = ::diesel_filter::cursor::after(&columns)
+                            .into_iter()
+                            .map(|(sql, value)| (sql, value.map(str::to_owned)))
+                            .collect();
+                        query = query.filter(After(after));
+                    }
+                    let mut rows = query.load::<#loaded>(conn)?;
+                    let next = match rows.len() as i64 > limit {
+                        true => {
+                            rows.truncate(limit.max(0) as usize);
+                            rows.last()
+                                .map(|row| {
+                                    let mut values = keys
+                                        .iter()
+                                        .map(|key| match *key {
+                                            #( #value_arms )*
+                                            _ => None,
+                                        })
+                                        .collect::<Vec<_>>();
+                                    #( values.push(Some(row.#names.to_string())); )*
+                                    values
+                                })
+                                .map(|values| Cursor { sort, values }.to_string())
+                        }
+                        false => None,
+                    };
+                    Ok((rows, next))
+                }
+            }
+        }
+        _ => quote! {},
+    };
+
+    let (filtered_ids, filtered_in_batches) = match primary_key_fields {
+        Some(keys) if loadable => {
+            let columns = keys
+                .iter()
+                .map(|(_, column, _)| quote! { #table::#column })
+                .collect::<Vec<_>>();
+            let tys = keys.iter().map(|(_, _, ty)| ty);
+            let (select, id_ty) = match keys.as_slice() {
+                [(_, column, ty)] => (quote! { #table::#column }, quote! { #ty }),
+                _ => (quote! { (#( #columns ),*) }, quote! { (#( #tys ),*) }),
+            };
+            let (output, load) = match pagination {
+                true => (
+                    quote! { (Vec<#id_ty>, i64) },
+                    quote! {
+                        .paginate(#page)
+                        .per_page(#per_page)
+                        .load_and_count::<#id_ty>(conn)
+                    },
+                ),
+                false => (
+                    quote! { Vec<#id_ty> },
+                    quote! { #limit .load::<#id_ty>(conn) },
+                ),
+            };
+
+            // The rows after the last key of the previous batch: `a > $1 OR (a = $1 AND b > $2)`
+            let names = keys.iter().map(|(name, _, _)| name).collect::<Vec<_>>();
+            let last = names
+                .iter()
+                .map(|name| Ident::new(&format!("last_{}", name), name.span()))
+                .collect::<Vec<_>>();
+            let after = (0..keys.len())
+                .map(|i| {
+                    let (column, key) = (&columns[i], &last[i]);
+                    columns[..i].iter().zip(&last[..i]).rev().fold(
+                        quote! { #column.gt(#key.clone()) },
+                        |after, (column, key)| quote! { #column.eq(#key.clone()).and(#after) },
+                    )
+                })
+                .reduce(|after, next| quote! { (#after).or(#next) })
+                .unwrap();
+
+            (
+                quote! {
+                    /// The primary keys of the rows `filtered` would load, e.g to load them
+                    /// with their associations afterwards without loading the rows twice
+                    pub fn filtered_ids(filters: &#filter_struct_ident, conn: &mut ::diesel::pg::PgConnection) -> Result<#output, ::diesel::result::Error> {
+                        Self::apply_filters(#table::table.select(#select).into_boxed(), filters)
+                            #load
+                    }
+                },
+                quote! {
+                    /// Calls `f` with every row matching the filters, by batches of `batch_size`
+                    /// rows ordered by primary key. Batches start after the last key of the
+                    /// previous one rather than at an offset, which gets slower on every page
+                    pub fn filtered_in_batches<F, E>(
+                        filters: &#filter_struct_ident,
+                        batch_size: i64,
+                        conn: &mut ::diesel::pg::PgConnection,
+                        mut f: F,
+                    ) -> Result<(), E>
+                    where
+                        F: FnMut(Vec<#loaded>) -> Result<(), E>,
+                        E: From<::diesel::result::Error>,
+                    {
+                        let mut after: Option<#id_ty> = None;
+                        loop {
+                            let mut query = Self::apply_filters(#table::table.into_boxed(), filters)
+                                .order_by((#( #columns.asc(), )*))
+                                .limit(batch_size);
+                            if let Some((#( #last ),*)) = after {
+                                query = query.filter(#after);
+                            }
+                            let rows = query.load::<#loaded>(conn)?;
+                            let done = (rows.len() as i64) < batch_size || rows.is_empty();
+                            after = rows.last().map(|row| (#( row.#names.clone() ),*));
+                            if !rows.is_empty() {
+                                f(rows)?;
+                            }
+                            if done {
+                                return Ok(());
+                            }
+                        }
+                    }
+                },
+            )
+        }
+        _ => (quote! {}, quote! {}),
+    };
+
+    let facets_impl = match facets.is_empty() {
+        true => quote! {},
+        false => {
+            let facets_ident = Ident::new(&format!("{}Facets", struct_name), struct_name.span());
+            let names = facets.iter().map(|(name, _, _)| name).collect::<Vec<_>>();
+            let values = names
+                .iter()
+                .map(|name| Ident::new(&format!("{}_value", name), name.span()))
+                .collect::<Vec<_>>();
+            let counts = names
+                .iter()
+                .map(|name| Ident::new(&format!("{}_counts", name), name.span()))
+                .collect::<Vec<_>>();
+            let columns = facets
+                .iter()
+                .map(|(_, column, _)| column)
+                .collect::<Vec<_>>();
+            let tys = facets.iter().map(|(_, _, ty)| ty).collect::<Vec<_>>();
+            let facets_derives = match serde {
+                true => {
+                    quote! { #[derive(::serde::Serialize, ::std::fmt::Debug, ::std::clone::Clone)] }
+                }
+                false => quote! { #[derive(::std::fmt::Debug, ::std::clone::Clone)] },
+            };
+
+            quote! {
+                /// The number of rows matching the filters per value of the `facet` filters,
+                /// most frequent values first
+                #facets_derives
+                pub struct #facets_ident {
+                    #( pub #names: Vec<(#tys, i64)>, )*
+                }
+
+                impl #impl_generics #struct_name #ty_generics #where_clause {
+                    /// Counts the rows matching the filters per value of the `facet` filters,
+                    /// with a single query grouped by all of their columns
+                    #[allow(deprecated)]
+                    pub fn filtered_facets(filters: &#filter_struct_ident, conn: &mut ::diesel::pg::PgConnection) -> Result<#facets_ident, ::diesel::result::Error> {
                         #( #uses )*
-                        let mut query = crate::schema::#table_name::table.into_boxed();
+                        let mut query = #table::table
+                            .group_by((#( #columns, )*))
+                            .select((#( #columns, )* ::diesel::dsl::count_star()))
+                            .into_boxed();
 
                         #( #queries )*
 
-                        query
+                        let rows = query.load::<(#( #tys, )* i64)>(conn)?;
+                        #( let mut #counts = ::std::collections::HashMap::<#tys, i64>::new(); )*
+                        for (#( #values, )* count) in rows {
+                            #( *#counts.entry(#values).or_insert(0) += count; )*
+                        }
+                        fn by_count<T>(counts: ::std::collections::HashMap<T, i64>) -> Vec<(T, i64)> {
+                            let mut counts = counts.into_iter().collect::<Vec<_>>();
+                            counts.sort_by(|a, b| b.1.cmp(&a.1));
+                            counts
+                        }
+                        Ok(#facets_ident {
+                            #( #names: by_count(#counts), )*
+                        })
+                    }
+                }
+            }
+        }
+    };
+
+    let expr_impl = match cfg!(feature = "expr") {
+        true => {
+            let expr_derives = match serde {
+                true => quote! {
+                    #[derive(::serde::Deserialize, ::serde::Serialize, ::std::fmt::Debug, ::std::clone::Clone)]
+                    #[serde(rename_all = "snake_case")]
+                },
+                false => quote! { #[derive(::std::fmt::Debug, ::std::clone::Clone)] },
+            };
+
+            quote! {
+                /// A condition on one of the filterable fields, combined in a `diesel_filter::FilterExpr`
+                #expr_derives
+                pub enum #filter_ident {
+                    #( #variants )*
+                }
+
+                impl #impl_generics #struct_name #ty_generics #where_clause {
+                    /// Filters the query with a boolean tree of conditions on the filterable fields
+                    pub fn apply_expr<'query>(
+                        query: #table::BoxedQuery<'query, ::diesel::pg::Pg>,
+                        expr: &'query ::diesel_filter::FilterExpr<#filter_ident>,
+                    ) -> #table::BoxedQuery<'query, ::diesel::pg::Pg> {
+                        ::diesel::QueryDsl::filter(query, Self::expr_predicate(expr))
+                    }
+
+                    #[doc(hidden)]
+                    #[allow(deprecated)]
+                    pub fn expr_predicate<'query>(
+                        expr: &'query ::diesel_filter::FilterExpr<#filter_ident>,
+                    ) -> Box<
+                        dyn ::diesel::BoxableExpression<
+                                #table::table,
+                                ::diesel::pg::Pg,
+                                SqlType = ::diesel::sql_types::Nullable<::diesel::sql_types::Bool>,
+                            > + 'query,
+                    > {
+                        #( #uses )*
+                        type Predicate<'query> = Box<
+                            dyn ::diesel::BoxableExpression<
+                                    #table::table,
+                                    ::diesel::pg::Pg,
+                                    SqlType = ::diesel::sql_types::Nullable<::diesel::sql_types::Bool>,
+                                > + 'query,
+                        >;
+
+                        match expr {
+                            ::diesel_filter::FilterExpr::And(exprs) => match exprs.split_first() {
+                                Some((first, rest)) => rest.iter().fold(
+                                    Self::expr_predicate(first),
+                                    |predicate, expr| -> Predicate<'query> {
+                                        Box::new(::diesel::BoolExpressionMethods::and(
+                                            predicate,
+                                            Self::expr_predicate(expr),
+                                        ))
+                                    },
+                                ),
+                                None => Box::new(::diesel::NullableExpressionMethods::nullable(
+                                    ::diesel::IntoSql::into_sql::<::diesel::sql_types::Bool>(true),
+                                )),
+                            },
+                            ::diesel_filter::FilterExpr::Or(exprs) => match exprs.split_first() {
+                                Some((first, rest)) => rest.iter().fold(
+                                    Self::expr_predicate(first),
+                                    |predicate, expr| -> Predicate<'query> {
+                                        Box::new(::diesel::BoolExpressionMethods::or(
+                                            predicate,
+                                            Self::expr_predicate(expr),
+                                        ))
+                                    },
+                                ),
+                                None => Box::new(::diesel::NullableExpressionMethods::nullable(
+                                    ::diesel::IntoSql::into_sql::<::diesel::sql_types::Bool>(false),
+                                )),
+                            },
+                            ::diesel_filter::FilterExpr::Not(expr) => {
+                                Box::new(::diesel::dsl::not(Self::expr_predicate(expr)))
+                            }
+                            ::diesel_filter::FilterExpr::Filter(filter) => match filter {
+                                #( #predicates )*
+                            },
+                        }
                     }
                 }
             }
         }
+        false => quote! {},
+    };
+
+    // The checks of `diesel_filter_tests!`, only built along with the tests of the crate
+    let unset =
+        quote! { let unset = || #filter_struct_ident { #( #field_cfgs #field_names: None, )* }; };
+    let test_sorts = match sortables.is_empty() {
+        true => quote! {},
+        false => {
+            let near = match near {
+                true => quote! {
+                    filters.near_lat = Some(0.0);
+                    filters.near_lng = Some(0.0);
+                },
+                false => quote! {},
+            };
+            quote! {
+                #unset
+                for key in [#( #sort_keys ),*].iter() {
+                    let mut filters = unset();
+                    filters.sort = Some(key.to_string());
+                    #near
+                    let sql = Self::filter_sql(&filters);
+                    assert!(sql.contains(" ORDER BY "), "the `{}` sort doesn't order the rows: {}", key, sql);
+                }
+            }
+        }
+    };
+    let test_pages = match pagination {
+        true => quote! {
+            #unset
+            let mut filters = unset();
+            filters.page = Some(3);
+            filters.per_page = Some(20);
+            let sql = ::diesel::debug_query::<::diesel::pg::Pg, _>(
+                &Self::filter(&filters).paginate(#page).per_page(#per_page),
+            )
+            .to_string();
+            let per_page = #per_page.unwrap_or(::diesel_filter::DEFAULT_PER_PAGE);
+            assert!(
+                sql.ends_with(&format!("{}, {}]", per_page, 2 * per_page)),
+                "the third page of {} rows isn't offset by {}: {}", per_page, 2 * per_page, sql
+            );
+            for &(total, pages) in [(0, 0), (1, 1), (20, 1), (21, 2)].iter() {
+                let info = ::diesel_filter::PageInfo::new(total, Some(20));
+                assert_eq!(info.total_pages, pages, "{} rows fill {} pages of 20", total, pages);
+            }
+        },
+        false => quote! {},
+    };
+    let templates = sql_templates
+        .into_iter()
+        .map(|(field, sql)| {
+            let cfg = cfg_of(&field);
+            let name = field.to_string().trim_start_matches("r#").to_owned();
+            quote! { #cfg (#name, #sql) }
+        })
+        .collect::<Vec<_>>();
+    // The filters only, the sorts, the pages and the loaders are Postgres ones
+    let sqlite_impl = match struct_opts.sqlite {
+        true => quote! {
+            /// `filter` for SQLite, e.g to run the models against a local database in
+            /// development. The filters have the same meaning, the sorts are left out
+            pub fn filter_sqlite<'query>(filters: &'query #filter_struct_ident) -> #table::BoxedQuery<'query, ::diesel::sqlite::Sqlite> {
+                Self::apply_filters_sqlite(#table::table.into_boxed(), filters)
+            }
+
+            /// `apply_filters` for SQLite
+            #[allow(deprecated)]
+            pub fn apply_filters_sqlite<'query, ST>(
+                mut query: #table::BoxedQuery<'query, ::diesel::sqlite::Sqlite, ST>,
+                filters: &'query #filter_struct_ident,
+            ) -> #table::BoxedQuery<'query, ::diesel::sqlite::Sqlite, ST> {
+                #( #uses )*
+
+                #( #sqlite_queries )*
+
+                query
+            }
+        },
+        false => quote! {},
+    };
+    let mysql_impl = match struct_opts.mysql {
+        true => quote! {
+            /// `filter` for MySQL, e.g to serve the same models from a MySQL replica.
+            /// The filters have the same meaning, the sorts are left out
+            pub fn filter_mysql<'query>(filters: &'query #filter_struct_ident) -> #table::BoxedQuery<'query, ::diesel::mysql::Mysql> {
+                Self::apply_filters_mysql(#table::table.into_boxed(), filters)
+            }
+
+            /// `apply_filters` for MySQL
+            #[allow(deprecated)]
+            pub fn apply_filters_mysql<'query, ST>(
+                mut query: #table::BoxedQuery<'query, ::diesel::mysql::Mysql, ST>,
+                filters: &'query #filter_struct_ident,
+            ) -> #table::BoxedQuery<'query, ::diesel::mysql::Mysql, ST> {
+                #( #uses )*
+
+                #( #mysql_queries )*
+
+                query
+            }
+        },
+        false => quote! {},
+    };
+    let test_support = quote! {
+        #[cfg(test)]
+        #[doc(hidden)]
+        pub fn __diesel_filter_test_filters() {
+            #unset
+            let sql = Self::filter_sql(&unset());
+            assert!(sql.ends_with("-- binds: []"), "unset filters bind values: {}", sql);
+            let cases: Vec<(&str, #filter_struct_ident, &str)> = vec![#( #test_cases ),*];
+            for (name, filters, fragment) in cases {
+                let sql = Self::filter_sql(&filters);
+                assert!(
+                    sql.contains(" WHERE ") && sql.contains(fragment),
+                    "the `{}` filter doesn't filter on {}: {}",
+                    name,
+                    fragment,
+                    sql
+                );
+            }
+        }
+
+        #[cfg(test)]
+        #[doc(hidden)]
+        pub fn __diesel_filter_test_sorts() {
+            #test_sorts
+        }
+
+        #[cfg(test)]
+        #[doc(hidden)]
+        pub fn __diesel_filter_test_pages() {
+            #test_pages
+        }
+    };
+
+    // The methods running the queries, left out for the repositories of their own
+    let loaders = match struct_opts.no_loaders {
+        true => quote! {},
+        false => quote! {
+            #filtered
+
+            #filtered_truncated
+
+            #filtered_chunked
+
+            #filtered_after
+
+            #filtered_ids
+
+            #filtered_latest
+
+            #filtered_union
+
+            #filtered_in_batches
+
+            /// The number of rows matching the filters, whatever the page
+            pub fn count_filtered(filters: &#filter_struct_ident, conn: &mut ::diesel::pg::PgConnection) -> Result<i64, ::diesel::result::Error> {
+                #count
+            }
+
+            #replica
+
+            #cache
+
+            #filtered_page_info
+
+            /// Loads the rows of a raw SQL query the DSL can't express, e.g a reporting
+            /// query, with the filters appended to `base_sql` as a `WHERE` clause of bound values.
+            /// `base_sql` ends with its `FROM` clause, or its `WHERE` clause the filters are added to
+            pub fn filtered_raw<'query, U>(
+                filters: &'query #filter_struct_ident,
+                base_sql: &str,
+                conn: &mut ::diesel::pg::PgConnection,
+            ) -> Result<Vec<U>, ::diesel::result::Error>
+            where
+                U: ::diesel::QueryableByName<::diesel::pg::Pg> + 'static,
+            {
+                // The condition of `base_sql` is kept apart from the filters, e.g its `OR`s
+                let where_clause = ::diesel_filter::sql::top_level_where(base_sql);
+                let mut query = match where_clause {
+                    Some(i) => ::diesel::sql_query(&base_sql[..i + "WHERE".len()])
+                        .into_boxed::<'query, ::diesel::pg::Pg>()
+                        .sql(" (")
+                        .sql(&base_sql[i + "WHERE".len()..])
+                        .sql(")"),
+                    None => ::diesel::sql_query(base_sql).into_boxed::<'query, ::diesel::pg::Pg>(),
+                };
+                let mut conditions: Vec<String> = vec![];
+                let mut params = 0;
+
+                #( #raw_conditions )*
+
+                if !conditions.is_empty() {
+                    let keyword = match where_clause {
+                        Some(_) => " AND ",
+                        None => " WHERE ",
+                    };
+                    query = query.sql(keyword).sql(conditions.join(" AND "));
+                }
+                query.load(conn)
+            }
+
+            #explain
+        },
+    };
+    let facets_impl = match struct_opts.no_loaders {
+        true => quote! {},
+        false => facets_impl,
+    };
+
+    let expanded = quote! {
+        const _: () = {
+            #( #column_checks )*
+        };
+
+        #filters_struct
+
+        #( #one_of_enums )*
+
+        #helpers_impl
+
+        #link_header_impl
+
+        #extractor_impl
+
+        #proto_impl
+
+        #rsql_impl
+
+        #odata_impl
+
+        #json_impl
+        #saved_search_impl
+
+        #defaults_impl
+
+        #search_impl
+
+        #warp_impl
+
+        #expr_impl
+
+        #facets_impl
+
+        const _: () = {
+            #trait_imports
+
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                #validate_filters
+
+                pub fn filter<'query>(filters: &'query #filter_struct_ident) -> #table::BoxedQuery<'query, ::diesel::pg::Pg> {
+                    #filter
+                }
+
+                /// Filters any boxed query of the table, whatever it selects, the rows of
+                /// `filter` and the count of `count_filtered` are filtered the same way
+                // `diesel::dsl::any` is deprecated, `LIKE ANY` has no replacement
+                #[allow(deprecated)]
+                pub fn apply_filters<'query, ST>(
+                    mut query: #table::BoxedQuery<'query, ::diesel::pg::Pg, ST>,
+                    filters: &'query #filter_struct_ident,
+                ) -> #table::BoxedQuery<'query, ::diesel::pg::Pg, ST> {
+                    #( #uses )*
+
+                    #( #queries )*
+
+                    query
+                }
+
+                #apply_sort
+
+                #sqlite_impl
+                #mysql_impl
+
+                /// The SQL of `filter` with its bound values, for logs and assertions
+                pub fn filter_sql(filters: &#filter_struct_ident) -> String {
+                    ::diesel::debug_query::<::diesel::pg::Pg, _>(&Self::filter(filters)).to_string()
+                }
+
+                /// The `WHERE` fragment of every filter set alone, as `filtered_raw` appends it, its
+                /// placeholders numbered per fragment from `$1`, or `$2` for the amount bounds
+                /// after the currency they need. `op` filters have no SQL of their own
+                pub const FILTER_SQL: &'static [(&'static str, &'static str)] = &[
+                    #( #templates, )*
+                ];
+
+                #test_support
+
+                #loaders
+            }
+        };
     };
-    TokenStream::from(expanded)
+
+    let expanded = match &struct_opts.module {
+        Some(module) => quote! {
+            #vis mod #module {
+                use super::*;
+
+                #expanded
+            }
+        },
+        None => expanded,
+    };
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proc_macro2::{Delimiter, TokenTree};
+
+    /// The fragments of the `FILTER_SQL` of the model
+    fn filter_sql(input: DeriveInput) -> Vec<(String, String)> {
+        fn fragments(tokens: proc_macro2::TokenStream) -> Option<proc_macro2::TokenStream> {
+            let mut tokens = tokens.into_iter();
+            while let Some(token) = tokens.next() {
+                match token {
+                    // The array after the type
+                    TokenTree::Ident(ident) if ident == "FILTER_SQL" => {
+                        tokens.find(|token| matches!(token, TokenTree::Punct(punct) if punct.as_char() == '='));
+                        return tokens.find_map(|token| match token {
+                            TokenTree::Group(group) if group.delimiter() == Delimiter::Bracket => {
+                                Some(group.stream())
+                            }
+                            _ => None,
+                        });
+                    }
+                    TokenTree::Group(group) => {
+                        if let Some(fragments) = fragments(group.stream()) {
+                            return Some(fragments);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            None
+        }
+
+        let expanded = expand(input).unwrap();
+        fragments(expanded)
+            .unwrap()
+            .into_iter()
+            .filter_map(|token| match token {
+                TokenTree::Group(group) => {
+                    let strings = group
+                        .stream()
+                        .into_iter()
+                        .filter_map(|token| syn::parse2::<LitStr>(token.into()).ok())
+                        .map(|lit| lit.value())
+                        .collect::<Vec<_>>();
+                    match strings.as_slice() {
+                        [name, sql] => Some((name.clone(), sql.clone())),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The signature and body of the generated function `name`, as a string of tokens
+    fn method(tokens: proc_macro2::TokenStream, name: &str) -> Option<String> {
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            match token {
+                TokenTree::Ident(ident) if ident == "fn" => {
+                    match tokens.peek() {
+                        Some(TokenTree::Ident(ident)) if ident == name => {}
+                        _ => continue,
+                    }
+                    let mut method = proc_macro2::TokenStream::new();
+                    for token in tokens.by_ref() {
+                        let body = matches!(&token, TokenTree::Group(group) if group.delimiter() == Delimiter::Brace);
+                        method.extend([token]);
+                        if body {
+                            return Some(method.to_string());
+                        }
+                    }
+                }
+                TokenTree::Group(group) => {
+                    if let Some(method) = method(group.stream(), name) {
+                        return Some(method);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn max_rows_caps_the_pages_of_every_method() {
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = readings)]
+            #[diesel_filter(latest_per = "device", by = "recorded_at", union(readings, readings_2023), max_rows = 2, validate)]
+            #[pagination(default_per_page = 50)]
+            pub struct Reading {
+                pub id: i32,
+                #[filter(multiple)]
+                pub device: String,
+                pub recorded_at: Option<chrono::NaiveDateTime>,
+            }
+        })
+        .unwrap();
+        let mut methods = vec![
+            "filtered",
+            "filtered_ids",
+            "filtered_latest",
+            "filtered_union",
+            "filtered_page_info",
+            "link_header",
+            "validate_filters",
+        ];
+        if cfg!(feature = "explain") {
+            methods.push("filtered_explain");
+        }
+        for name in methods {
+            let method = method(expanded.clone(), name).unwrap();
+            assert!(
+                method.contains(". unwrap_or (50)) . unwrap_or (:: diesel_filter :: DEFAULT_PER_PAGE) . min (2))"),
+                "`{}` doesn't cap `per_page`: {}",
+                name,
+                method
+            );
+        }
+    }
+
+    #[test]
+    fn max_rows_limits_the_rows_without_pagination() {
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = readings)]
+            #[diesel_filter(latest_per = "device", by = "recorded_at", union(readings, readings_2023), max_rows = 2)]
+            pub struct Reading {
+                pub id: i32,
+                #[filter(multiple)]
+                pub device: String,
+                pub recorded_at: Option<chrono::NaiveDateTime>,
+            }
+        })
+        .unwrap();
+        for name in [
+            "filtered",
+            "filtered_ids",
+            "filtered_latest",
+            "filtered_union",
+        ] {
+            let method = method(expanded.clone(), name).unwrap();
+            assert!(
+                method.contains(". limit (2)"),
+                "`{}` doesn't limit the rows: {}",
+                name,
+                method
+            );
+        }
+    }
+
+    #[test]
+    fn log_sql_logs_through_the_reexported_log() {
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = projects)]
+            #[diesel_filter(log_sql)]
+            pub struct Project {
+                pub id: i32,
+                #[filter]
+                pub name: String,
+            }
+        });
+        match cfg!(feature = "log") {
+            true => {
+                let filtered = method(expanded.unwrap(), "filtered").unwrap();
+                assert!(
+                    filtered.contains(":: diesel_filter :: __log :: debug !"),
+                    "{}",
+                    filtered
+                );
+                assert!(!filtered.contains(" log :: debug"), "{}", filtered);
+            }
+            false => assert!(expanded
+                .unwrap_err()
+                .to_string()
+                .contains("the `log` feature of diesel_filter")),
+        }
+    }
+
+    #[test]
+    fn generated_paths_are_absolute() {
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = projects)]
+            #[diesel_filter(schema_module = "db::tables", union(projects, archived_projects))]
+            pub struct Project {
+                pub id: i32,
+                #[filter]
+                pub name: String,
+            }
+        })
+        .unwrap()
+        .to_string();
+        assert!(!expanded.contains("crate :: schema"), "{}", expanded);
+        assert!(expanded.contains("db :: tables :: projects :: table"));
+        assert!(expanded.contains("db :: tables :: archived_projects :: table"));
+        for derive in expanded.split("derive (").skip(1) {
+            assert!(derive.starts_with("::"), "bare derive: {}", derive);
+        }
+        if cfg!(feature = "rocket") {
+            assert!(expanded.contains(":: rocket :: FromForm"));
+        }
+    }
+
+    #[test]
+    fn filter_sql_snapshot() {
+        let sql = filter_sql(parse_quote! {
+            #[diesel(table_name = projects)]
+            #[diesel_filter(money(amount = "budget", currency = "currency"))]
+            pub struct Project {
+                pub id: i32,
+                #[filter(substring, insensitive)]
+                pub name: String,
+                #[filter(multiple)]
+                pub status: String,
+                #[filter(gte)]
+                pub priority: i32,
+                pub budget: i64,
+                pub currency: String,
+            }
+        });
+        let sql = sql
+            .iter()
+            .map(|(name, sql)| (name.as_str(), sql.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sql,
+            [
+                ("name", r#""projects"."name" ILIKE ('%' || $1 || '%')"#),
+                ("status", r#""projects"."status" = ANY($1)"#),
+                ("priority", r#""projects"."priority" >= $1"#),
+                ("currency", r#""projects"."currency" = $1"#),
+                ("min_amount", r#""projects"."budget" >= $2"#),
+                ("max_amount", r#""projects"."budget" <= $2"#),
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_mysql_matches_fulltext_against_the_column() {
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = notes)]
+            #[diesel_filter(backends(pg, mysql))]
+            pub struct Note {
+                pub id: i32,
+                #[filter(substring, insensitive)]
+                pub title: String,
+                #[filter(fulltext)]
+                pub body: String,
+                #[filter(multiple)]
+                pub tag: String,
+            }
+        })
+        .unwrap();
+        let filter = method(expanded, "apply_filters_mysql").unwrap();
+        assert!(
+            filter.contains("\"MATCH (`notes`.`body`) AGAINST (\""),
+            "{}",
+            filter
+        );
+        assert!(filter.contains("\" IN BOOLEAN MODE)\""), "{}", filter);
+        assert!(filter.contains("CONCAT('%', "), "{}", filter);
+        assert!(filter.contains(". eq_any ("), "{}", filter);
+        assert!(!filter.contains("websearch_to_tsquery"), "{}", filter);
+    }
+
+    #[test]
+    fn mysql_rejects_the_filters_without_counterpart() {
+        let error = expand(parse_quote! {
+            #[diesel(table_name = notes)]
+            #[diesel_filter(backends(pg, mysql))]
+            pub struct Note {
+                pub id: i32,
+                #[filter(one_of("open", "closed"))]
+                pub status: String,
+            }
+        })
+        .unwrap_err();
+        assert!(error.to_string().contains("MySQL"), "{}", error);
+    }
+
+    #[test]
+    fn filter_sqlite_matches_the_fts5_table() {
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = notes)]
+            #[diesel_filter(backends(pg, sqlite))]
+            pub struct Note {
+                pub id: i32,
+                #[filter(fulltext, fts_table = "notes_fts")]
+                pub body: String,
+            }
+        })
+        .unwrap();
+        let filter = method(expanded, "apply_filters_sqlite").unwrap();
+        assert!(
+            filter.contains(
+                r#""\"notes\".rowid IN (SELECT rowid FROM notes_fts WHERE notes_fts MATCH ""#
+            ),
+            "{}",
+            filter
+        );
+        assert!(!filter.contains("websearch_to_tsquery"), "{}", filter);
+    }
+
+    #[test]
+    fn filter_sqlite_rejects_fulltext_without_fts_table() {
+        let error = expand(parse_quote! {
+            #[diesel(table_name = notes)]
+            #[diesel_filter(backends(pg, sqlite))]
+            pub struct Note {
+                pub id: i32,
+                #[filter(fulltext)]
+                pub body: String,
+            }
+        })
+        .unwrap_err();
+        assert!(error.to_string().contains("SQLite"), "{}", error);
+    }
+
+    #[test]
+    fn borrowed_fields_skip_the_loaders() {
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = projects)]
+            pub struct ProjectRef<'a> {
+                pub id: i32,
+                #[filter]
+                pub name: Option<&'a str>,
+                #[filter(substring)]
+                pub description: std::borrow::Cow<'a, str>,
+            }
+        })
+        .unwrap();
+        assert!(method(expanded.clone(), "filter").is_some());
+        assert!(method(expanded.clone(), "filtered").is_none());
+        assert!(method(expanded, "count_filtered").is_some());
+
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = projects)]
+            pub struct ProjectRef<'a> {
+                pub id: i32,
+                #[filter(substring)]
+                pub description: std::borrow::Cow<'a, str>,
+            }
+        })
+        .unwrap();
+        assert!(method(expanded, "filtered").is_some());
+    }
+
+    #[test]
+    fn filter_sqlite_falls_back_to_portable_operators() {
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = projects)]
+            #[diesel_filter(backends(pg, sqlite))]
+            pub struct Project {
+                pub id: i32,
+                #[filter(multiple)]
+                pub status: String,
+                #[filter(insensitive)]
+                pub owner: String,
+                #[filter(substring, insensitive)]
+                pub name: String,
+                #[filter(gte)]
+                pub priority: i32,
+            }
+        })
+        .unwrap();
+        let filter = method(expanded, "apply_filters_sqlite").unwrap();
+        assert!(
+            filter.contains("projects :: status . eq_any (filter)"),
+            "{}",
+            filter
+        );
+        assert!(
+            filter.contains("projects :: owner . like (filter)"),
+            "{}",
+            filter
+        );
+        assert!(filter.contains("\"('%' || \""), "{}", filter);
+        assert!(
+            filter.contains("projects :: priority . ge (filter)"),
+            "{}",
+            filter
+        );
+        assert!(!filter.contains("ilike"), "{}", filter);
+        assert!(!filter.contains("ANY"), "{}", filter);
+        assert!(
+            filter.contains(":: diesel :: sqlite :: Sqlite"),
+            "{}",
+            filter
+        );
+
+        let error = expand(parse_quote! {
+            #[diesel(table_name = projects)]
+            #[diesel_filter(backends(pg, sqlite))]
+            pub struct Project {
+                pub id: i32,
+                #[filter(multiple, substring)]
+                pub name: String,
+            }
+        })
+        .unwrap_err();
+        assert!(error.to_string().contains("SQLite"), "{}", error);
+    }
+
+    #[test]
+    fn filtered_union_filters_every_table() {
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = events)]
+            #[diesel_filter(union(events_2023, events_2022))]
+            pub struct Event {
+                pub id: i32,
+                #[filter]
+                pub kind: String,
+            }
+        })
+        .unwrap();
+        let union = method(expanded, "filtered_union").unwrap();
+        assert!(
+            union.contains(":: diesel_filter :: union :: UnionAll :: new (Self :: filter (filters) , \"events\""),
+            "{}",
+            union
+        );
+        assert!(
+            union.contains("& [\"events_2023\" , \"events_2022\"]"),
+            "{}",
+            union
+        );
+
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = events)]
+            pub struct Event {
+                pub id: i32,
+                #[filter]
+                pub kind: String,
+            }
+        })
+        .unwrap();
+        assert!(method(expanded, "filtered_union").is_none());
+    }
+
+    #[test]
+    fn filtered_after_orders_by_the_sort_then_the_key() {
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = posts)]
+            pub struct Post {
+                pub id: i32,
+                #[filter]
+                #[sortable]
+                pub title: String,
+            }
+        })
+        .unwrap();
+        let after = method(expanded, "filtered_after").unwrap();
+        assert!(
+            after.contains(
+                "\"(json_populate_record(NULL::\\\"posts\\\", json_build_object('title', \""
+            ),
+            "{}",
+            after
+        );
+        assert!(
+            after.contains("columns . push ((\"\\\"posts\\\".\\\"id\\\"\""),
+            "{}",
+            after
+        );
+        assert!(after.contains("\"title\" =>"), "{}", after);
+
+        // Without `sortable` fields there is no order to page through
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = posts)]
+            pub struct Post {
+                pub id: i32,
+                #[filter]
+                pub title: String,
+            }
+        })
+        .unwrap();
+        assert!(method(expanded, "filtered_after").is_none());
+    }
+
+    #[test]
+    fn filtered_facets_groups_by_the_facet_columns() {
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = projects)]
+            pub struct Project {
+                pub id: i32,
+                #[filter(facet)]
+                pub status: String,
+                #[filter(facet)]
+                pub priority: i32,
+                #[filter(substring)]
+                pub name: String,
+            }
+        })
+        .unwrap();
+        let facets = method(expanded.clone(), "filtered_facets").unwrap();
+        assert!(
+            facets.contains(". group_by ((crate :: schema :: projects :: status , crate :: schema :: projects :: priority ,))"),
+            "{}",
+            facets
+        );
+        assert!(facets.contains("count_star ()"), "{}", facets);
+        assert!(
+            facets.contains("load :: < (String , i32 , i64) >"),
+            "{}",
+            facets
+        );
+        let expanded = expanded.to_string();
+        assert!(
+            expanded.contains("pub struct ProjectFacets { pub status : Vec < (String , i64) > , pub priority : Vec < (i32 , i64) > , }"),
+            "{}",
+            expanded
+        );
+    }
+
+    #[test]
+    fn with_defaults_fills_the_unset_constraints() {
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = projects)]
+            #[diesel_filter(default_filters)]
+            pub struct Project {
+                pub id: i32,
+                #[filter]
+                pub region: String,
+                #[filter]
+                #[sortable]
+                pub name: String,
+            }
+        })
+        .unwrap();
+        let defaults = method(expanded.clone(), "with_defaults").unwrap();
+        assert!(
+            defaults.contains("region : self . region . or_else"),
+            "{}",
+            defaults
+        );
+        assert!(
+            defaults.contains("defaults . region . clone ()"),
+            "{}",
+            defaults
+        );
+        assert!(
+            defaults.contains("name : self . name . or_else"),
+            "{}",
+            defaults
+        );
+        assert!(defaults.contains("sort : self . sort ,"), "{}", defaults);
+        assert!(method(expanded, "with_default_filters").is_some());
+    }
+
+    #[test]
+    fn filtered_chunked_runs_a_query_per_chunk() {
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = projects)]
+            #[diesel_filter(max_rows = 100)]
+            pub struct Project {
+                #[filter(multiple, chunk_size = 1000)]
+                pub id: i32,
+                #[filter(substring)]
+                pub name: String,
+            }
+        })
+        .unwrap();
+        let chunked = method(expanded, "filtered_chunked").unwrap();
+        assert!(
+            chunked.contains("Some (values) if values . len () > 1000usize => values"),
+            "{}",
+            chunked
+        );
+        assert!(
+            chunked.contains("for chunk in values . chunks (1000usize)"),
+            "{}",
+            chunked
+        );
+        assert!(
+            chunked.contains("id : Some (chunk . to_vec ()) , name : filters . name . clone () ,"),
+            "{}",
+            chunked
+        );
+        assert!(chunked.contains("rows . truncate (100)"), "{}", chunked);
+
+        let expanded = expand(parse_quote! {
+            #[diesel(table_name = projects)]
+            pub struct Project {
+                #[filter(multiple)]
+                pub id: i32,
+            }
+        })
+        .unwrap();
+        assert!(method(expanded, "filtered_chunked").is_none());
+    }
+
+    #[test]
+    fn chunk_size_splits_equality_filters_only() {
+        for input in [
+            quote! {
+                #[diesel(table_name = projects)]
+                pub struct Project {
+                    #[filter(chunk_size = 1000)]
+                    pub id: i32,
+                }
+            },
+            quote! {
+                #[diesel(table_name = projects)]
+                pub struct Project {
+                    pub id: i32,
+                    #[filter(multiple, substring, chunk_size = 1000)]
+                    pub name: String,
+                }
+            },
+            quote! {
+                #[diesel(table_name = projects)]
+                pub struct Project {
+                    #[filter(multiple, chunk_size = 1000, max_values = 2000)]
+                    pub id: i32,
+                }
+            },
+            quote! {
+                #[diesel(table_name = projects)]
+                pub struct Project {
+                    #[filter(multiple, chunk_size = 1000)]
+                    pub id: i32,
+                    #[filter(multiple, chunk_size = 1000)]
+                    pub owner_id: i32,
+                }
+            },
+        ] {
+            assert!(expand(syn::parse2(input).unwrap()).is_err());
+        }
+    }
+
+    #[test]
+    fn numbered_sql_numbers_the_placeholders() {
+        assert_eq!(numbered_sql("a = ${} AND b = ${}"), "a = $1 AND b = $2");
+        assert_eq!(numbered_sql("a = ${} OR a = ${0}"), "a = $1 OR a = $1");
+        assert_eq!(numbered_sql("'{{}}' = ${}"), "'{}' = $1");
+        assert_eq!(numbered_sql(""), "");
+    }
 }